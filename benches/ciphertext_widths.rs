@@ -0,0 +1,85 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use tfhe::prelude::*;
+use tfhe::{generate_keys, set_server_key, ConfigBuilder, FheUint16, FheUint32, FheUint64};
+use tfhe_gps_distance::{default_demo_points, Point, KM_PER_DEGREE, SCALE_FACTOR};
+
+// Basel (X) and Lugano (Y) against Zurich (Z): deltas of roughly one to two degrees
+fn points() -> [Point; 3] {
+    default_demo_points().map(|(_, point)| point)
+}
+
+/// Plaintext planar distance in degrees, the reference for the reported error.
+fn planar_degrees(a: &Point, b: &Point) -> f64 {
+    ((a.lat - b.lat).powi(2) + (a.lon - b.lon).powi(2)).sqrt()
+}
+
+// Each width gets the largest power-of-ten scale whose squared distances still fit, so the
+// benchmark measures what the extra headroom costs and what precision it buys.
+macro_rules! bench_width {
+    ($c:expr, $client_key:expr, $fhe:ty, $clear:ty, $scale:expr, $id:expr) => {{
+        let scale: f64 = $scale;
+        let [x, y, z] = points().map(|p| {
+            (
+                <$fhe>::encrypt((p.lat * scale) as $clear, $client_key),
+                <$fhe>::encrypt((p.lon * scale) as $clear, $client_key),
+            )
+        });
+        let squared_distance = |a: &($fhe, $fhe), b: &($fhe, $fhe)| -> $fhe {
+            let d_lat = &a.0 - &b.0;
+            let d_lon = &a.1 - &b.1;
+            &(&d_lat * &d_lat) + &(&d_lon * &d_lon)
+        };
+
+        let decrypted: $clear = squared_distance(&x, &z).decrypt($client_key);
+        let fhe_km = (decrypted as f64).sqrt() / scale * KM_PER_DEGREE;
+        let expected_km = planar_degrees(&points()[0], &points()[2]) * KM_PER_DEGREE;
+        println!(
+            "{}: scale {}, X-Z distance error {:.3} km",
+            $id,
+            scale,
+            (fhe_km - expected_km).abs()
+        );
+
+        $c.bench_function($id, |b| {
+            b.iter(|| squared_distance(&x, &z).lt(&squared_distance(&y, &z)))
+        });
+    }};
+}
+
+fn ciphertext_widths(c: &mut Criterion) {
+    let config = ConfigBuilder::default().build();
+    let (client_key, server_keys) = generate_keys(config);
+    set_server_key(server_keys);
+
+    bench_width!(
+        c,
+        &client_key,
+        FheUint16,
+        u16,
+        100.0,
+        "ciphertext_width/u16"
+    );
+    bench_width!(
+        c,
+        &client_key,
+        FheUint32,
+        u32,
+        SCALE_FACTOR,
+        "ciphertext_width/u32"
+    );
+    bench_width!(
+        c,
+        &client_key,
+        FheUint64,
+        u64,
+        1_000_000.0,
+        "ciphertext_width/u64"
+    );
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = ciphertext_widths
+}
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use tfhe::{generate_keys, ConfigBuilder};
+use tfhe_gps_distance::{ClientData, DistanceMode, Point};
+
+// A spread of points across both hemispheres, so every encoding path is exercised
+fn points() -> Vec<Point> {
+    (0..256)
+        .map(|i| Point::new(-85.0 + 0.66 * i as f64, -179.0 + 1.4 * i as f64))
+        .collect()
+}
+
+// Run once with and once without `--features parallel` to read off the speedup.
+fn encrypt_batch(c: &mut Criterion) {
+    let (client_key, _) = generate_keys(ConfigBuilder::default().build());
+    let points = points();
+
+    let id = if cfg!(feature = "parallel") {
+        "encrypt_batch_256_parallel"
+    } else {
+        "encrypt_batch_256_sequential"
+    };
+    c.bench_function(id, |b| {
+        b.iter(|| ClientData::encrypt_batch(&points, DistanceMode::Degrees, &client_key))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = encrypt_batch
+}
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+use serde::Serialize;
+use tfhe::prelude::*;
+use tfhe::{
+    generate_keys, CompressedFheBool, CompressedFheUint32, CompressedServerKey, ConfigBuilder,
+    FheBool, FheUint32,
+};
+use tfhe_gps_distance::{default_demo_points, ClientData};
+
+fn size<T: Serialize>(value: &T) -> usize {
+    bincode::serialized_size(value).expect("value should serialize") as usize
+}
+
+// Prints the bincode size of every form a point, a result or a key can travel in, so
+// bandwidth trade-offs can be read off one table. The crate has no compressed `ClientData`,
+// so that row shows two `CompressedFheUint32` instead. The sizes are checked by
+// `compressed_forms_are_smaller` in `src/wire.rs`.
+fn main() {
+    let config = ConfigBuilder::default().build();
+    let (client_key, server_key) = generate_keys(config);
+    let compressed_server_key = CompressedServerKey::new(&client_key);
+
+    let point = default_demo_points()[0].1;
+    let x = ClientData::encrypt(&point, &client_key).expect("demo point should encrypt");
+    let (lat, _) = point.scaled();
+
+    let rows = [
+        (
+            "FheUint32",
+            size(&FheUint32::encrypt(lat, &client_key)),
+            "CompressedFheUint32",
+            size(&CompressedFheUint32::encrypt(lat, &client_key)),
+        ),
+        (
+            "ClientData",
+            size(&x),
+            "2 x CompressedFheUint32",
+            2 * size(&CompressedFheUint32::encrypt(lat, &client_key)),
+        ),
+        (
+            "FheBool",
+            size(&FheBool::encrypt(true, &client_key)),
+            "CompressedFheBool",
+            size(&CompressedFheBool::encrypt(true, &client_key)),
+        ),
+        (
+            "ServerKey",
+            size(&server_key),
+            "CompressedServerKey",
+            size(&compressed_server_key),
+        ),
+    ];
+
+    println!(
+        "{:<12} {:>14}   {:<24} {:>14}",
+        "form", "bytes", "compressed form", "bytes"
+    );
+    for (name, bytes, compressed_name, compressed_bytes) in rows {
+        println!(
+            "{:<12} {:>14}   {:<24} {:>14}",
+            name, bytes, compressed_name, compressed_bytes
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use clap::{Parser, Subcommand};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::time::Instant;
+use tfhe::prelude::*;
+use tfhe::{ClientKey, CompressedServerKey, ConfigBuilder, FheBool};
+use tfhe_gps_distance::{default_demo_points, read_frame, write_frame, ClientData, QueryPackage};
+
+/// Encrypts the demo points, has a server compare them, and decrypts the answer
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// Address of the server
+    #[arg(long, default_value = "127.0.0.1:7878")]
+    server: String,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate keys and write the query for `server --query` to a file
+    Prepare {
+        /// File to keep the client key in; it never needs to leave this machine
+        #[arg(long, value_name = "FILE")]
+        key: PathBuf,
+        /// File to write the server key and encrypted points to
+        #[arg(long, value_name = "FILE")]
+        query: PathBuf,
+    },
+    /// Decrypt an answer written by `server --output`
+    Decrypt {
+        /// Client key written by `prepare`
+        #[arg(long, value_name = "FILE")]
+        key: PathBuf,
+        /// Encrypted answer from the server
+        #[arg(long, value_name = "FILE")]
+        result: PathBuf,
+    },
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        None => round_trip(&cli.server),
+        Some(Command::Prepare { key, query }) => {
+            let (client_key, package) = prepare()?;
+            write_frame(BufWriter::new(File::create(&key)?), &client_key)?;
+            write_frame(BufWriter::new(File::create(&query)?), &package)?;
+            println!(
+                "Wrote query to {}; keep {} private.",
+                query.display(),
+                key.display()
+            );
+            Ok(())
+        }
+        Some(Command::Decrypt { key, result }) => {
+            let client_key: ClientKey = read_frame(BufReader::new(File::open(key)?))?;
+            let closer_x: FheBool = read_frame(BufReader::new(File::open(result)?))?;
+            report(&closer_x, &client_key);
+            Ok(())
+        }
+    }
+}
+
+/// Generates the keys locally and encrypts Basel (X), Lugano (Y) and Zurich (Z).
+fn prepare() -> Result<(ClientKey, QueryPackage), Box<dyn std::error::Error>> {
+    let config = ConfigBuilder::default().build();
+    let client_key = ClientKey::generate(config);
+    let server_key = CompressedServerKey::new(&client_key);
+
+    let [(_, x), (_, y), (_, z)] = default_demo_points();
+    let package = QueryPackage {
+        server_key,
+        x: ClientData::encrypt(&x, &client_key)?,
+        y: ClientData::encrypt(&y, &client_key)?,
+        z: ClientData::encrypt(&z, &client_key)?,
+    };
+
+    Ok((client_key, package))
+}
+
+fn report(closer_x: &FheBool, client_key: &ClientKey) {
+    let [(x_name, _), (y_name, _), (z_name, _)] = default_demo_points();
+    let is_x_closer: bool = closer_x.decrypt(client_key);
+    let closer = if is_x_closer { x_name } else { y_name };
+    println!("{} is closer to {}.", closer, z_name);
+}
+
+fn round_trip(server: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // Only the derived server key is sent; the client key stays here
+    let (client_key, package) = prepare()?;
+
+    println!("Sending encrypted query to {}...", server);
+    let start_time = Instant::now();
+    let mut stream = TcpStream::connect(server)?;
+    write_frame(&mut stream, &package)?;
+    let closer_x: FheBool = read_frame(&mut stream)?;
+    let duration = start_time.elapsed();
+
+    report(&closer_x, &client_key);
+    println!(
+        "Round trip time (including server computation): {:?}",
+        duration
+    );
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use clap::Parser;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::net::{TcpListener, TcpStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tfhe::{set_server_key, FheBool};
+use tfhe_gps_distance::{
+    compare_distances_global, read_frame, write_frame, GpsFheError, QueryPackage,
+};
+
+/// Answers encrypted distance comparisons without ever seeing a client key
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:7878", conflicts_with = "query")]
+    listen: String,
+    /// Answer a single query read from this file instead of listening
+    #[arg(long, value_name = "FILE", requires = "output")]
+    query: Option<PathBuf>,
+    /// File to write the encrypted answer to, with --query
+    #[arg(long, value_name = "FILE", requires = "query")]
+    output: Option<PathBuf>,
+    /// Most connections answered at once; each holds a full server key in memory
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    workers: u16,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    if let (Some(query), Some(output)) = (cli.query, cli.output) {
+        let query: QueryPackage = read_frame(BufReader::new(File::open(query)?))?;
+        println!("Read query, computing...");
+        let closer_x = answer(query);
+        write_frame(BufWriter::new(File::create(&output)?), &closer_x)?;
+        println!("Wrote encrypted result to {}.", output.display());
+        return Ok(());
+    }
+
+    let listener = TcpListener::bind(&cli.listen)?;
+    println!("Listening on {}...", listener.local_addr()?);
+
+    // A fixed pool of workers takes connections one at a time, so at most `workers` server
+    // keys are in memory; further connections wait in the listen backlog
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(0);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..cli.workers {
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || work(&receiver));
+    }
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => sender.send(stream)?,
+            Err(e) => eprintln!("Failed to accept a connection: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Answers connections from `receiver` until the channel closes.
+fn work(receiver: &Mutex<Receiver<TcpStream>>) {
+    loop {
+        // Release the lock before handling, so other workers can take the next connection
+        let stream = match receiver.lock().unwrap().recv() {
+            Ok(stream) => stream,
+            Err(_) => return,
+        };
+        // Each worker installs its current client's server key on its own thread. A panic
+        // while answering only fails that request, so the pool keeps its size
+        let peer = stream.peer_addr().ok();
+        match panic::catch_unwind(AssertUnwindSafe(|| handle(stream))) {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => eprintln!("Request from {:?} failed: {}", peer, e),
+            Err(_) => eprintln!("Request from {:?} panicked", peer),
+        }
+    }
+}
+
+/// Installs the query's server key on this thread and compares its points.
+///
+/// The server cannot see the points to check their range, so it uses the global comparison,
+/// which stays correct for points anywhere on the globe.
+fn answer(query: QueryPackage) -> FheBool {
+    // The server key is thread-local, so set it for the thread doing the work
+    set_server_key(query.server_key.decompress());
+    compare_distances_global(&query.x, &query.y, &query.z)
+}
+
+fn handle(mut stream: TcpStream) -> Result<(), GpsFheError> {
+    let query: QueryPackage = read_frame(&mut stream)?;
+    println!("Received query, computing...");
+
+    let closer_x = answer(query);
+
+    write_frame(&mut stream, &closer_x)?;
+    println!("Sent encrypted result.");
+    Ok(())
+}
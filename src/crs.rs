@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+use crate::{GpsFheError, Point};
+
+/// Equatorial radius of the WGS-84 ellipsoid used by Web Mercator, in meters.
+const WEB_MERCATOR_RADIUS_M: f64 = 6_378_137.0;
+
+/// Coordinate reference system of raw point input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Crs {
+    /// Decimal degrees as `(lat, lon)`.
+    Wgs84,
+    /// Web Mercator (EPSG:3857) meters as `(x, y)`.
+    WebMercator,
+    /// Any other reference system, identified by name. Always rejected.
+    Custom(&'static str),
+}
+
+impl Point {
+    /// Builds a WGS-84 point from raw coordinates in the given reference system.
+    ///
+    /// Web Mercator input is converted with the closed-form inverse projection. Unknown
+    /// systems are rejected rather than guessed, and degree input outside the valid range,
+    /// NaN included, is reported as `GpsFheError::OutOfRange`.
+    pub fn from_crs(first: f64, second: f64, crs: Crs) -> Result<Point, GpsFheError> {
+        let point = match crs {
+            Crs::Wgs84 => Point::new(first, second),
+            Crs::WebMercator => {
+                let lon = (first / WEB_MERCATOR_RADIUS_M).to_degrees();
+                let lat =
+                    (2.0 * (second / WEB_MERCATOR_RADIUS_M).exp().atan() - PI / 2.0).to_degrees();
+                Point::new(lat, lon)
+            }
+            Crs::Custom(name) => return Err(GpsFheError::UnsupportedCrs(name)),
+        };
+
+        if !(-90.0..=90.0).contains(&point.lat) || !(-180.0..=180.0).contains(&point.lon) {
+            return Err(GpsFheError::OutOfRange {
+                lat: point.lat,
+                lon: point.lon,
+            });
+        }
+
+        Ok(point)
+    }
+}
+
+/// Plaintext latitude/longitude rectangle in decimal degrees, used as a plausibility hint.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+}
+
+impl BoundingBox {
+    pub fn new(min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Self {
+        BoundingBox {
+            min_lat,
+            min_lon,
+            max_lat,
+            max_lon,
+        }
+    }
+
+    /// Returns true if the point lies within the box, edges included.
+    pub fn contains(&self, point: &Point) -> bool {
+        (self.min_lat..=self.max_lat).contains(&point.lat)
+            && (self.min_lon..=self.max_lon).contains(&point.lon)
+    }
+}
+
+impl Point {
+    /// Checks the point against the region it is expected to fall in, before it is encrypted.
+    ///
+    /// Returns `GpsFheError::LikelyLatLonSwapped` if the point lies outside `hint` but would
+    /// lie inside it with latitude and longitude exchanged. Points that fit the hint, and
+    /// points that fit it in neither order, are accepted: the hint only targets the ordering
+    /// mistake, not points that are merely elsewhere.
+    pub fn check_order(&self, hint: &BoundingBox) -> Result<(), GpsFheError> {
+        let swapped = Point::new(self.lon, self.lat);
+        if !hint.contains(self) && hint.contains(&swapped) {
+            return Err(GpsFheError::LikelyLatLonSwapped {
+                lat: self.lat,
+                lon: self.lon,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Basel, Lugano and Zurich in Web Mercator meters, from the forward projection
+    const MERCATOR_DEMO: [(f64, f64); 3] = [
+        (844_759.087_833_835_9, 6_033_898.211_343_567),
+        (996_431.894_039_671, 5_780_942.167_676_218),
+        (950_857.694_508_904_9, 6_003_812.204_877_848),
+    ];
+
+    #[test]
+    fn web_mercator_demo_points_encode_like_their_degrees() {
+        for ((x, y), (name, degrees)) in MERCATOR_DEMO.iter().zip(crate::default_demo_points()) {
+            let converted = Point::from_crs(*x, *y, Crs::WebMercator).unwrap();
+            assert!(
+                (converted.lat - degrees.lat).abs() < 1e-9
+                    && (converted.lon - degrees.lon).abs() < 1e-9,
+                "{} converted to {:?}",
+                name,
+                converted
+            );
+            // Identical encodings give identical encrypted comparisons
+            assert_eq!(converted.scaled(), degrees.scaled(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn meters_given_as_degrees_suggest_a_projected_crs() {
+        let error = Point::from_crs(6_003_812.2, 950_857.7, Crs::Wgs84).unwrap_err();
+        assert!(matches!(error, GpsFheError::OutOfRange { .. }));
+        assert!(error.to_string().contains("may be in meters"), "{}", error);
+    }
+
+    #[test]
+    fn nan_coordinates_are_out_of_range() {
+        assert!(matches!(
+            Point::from_crs(f64::NAN, 8.5417, Crs::Wgs84),
+            Err(GpsFheError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            Point::from_crs(47.3769, f64::NAN, Crs::Wgs84),
+            Err(GpsFheError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn custom_crs_is_rejected() {
+        assert_eq!(
+            Point::from_crs(2_683_000.0, 1_247_000.0, Crs::Custom("EPSG:2056")),
+            Err(GpsFheError::UnsupportedCrs("EPSG:2056"))
+        );
+    }
+
+    #[test]
+    fn swapped_basel_is_flagged_against_a_switzerland_hint() {
+        let switzerland = BoundingBox::new(45.8, 5.9, 47.9, 10.5);
+
+        assert_eq!(
+            Point::new(7.5886, 47.5596).check_order(&switzerland),
+            Err(GpsFheError::LikelyLatLonSwapped {
+                lat: 7.5886,
+                lon: 47.5596
+            })
+        );
+        assert_eq!(
+            Point::new(47.5596, 7.5886).check_order(&switzerland),
+            Ok(())
+        );
+        // Tokyo is outside the hint in either order, so it is not reported as swapped
+        assert_eq!(
+            Point::new(35.6762, 139.6503).check_order(&switzerland),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use serde_json::json;
+
+use crate::{haversine_km, GpsFheError, Point};
+
+/// Opt-in JSONL log of comparisons where the encrypted result disagrees with the plaintext
+/// great-circle baseline.
+///
+/// Each divergent `(x, y, z)` triple is appended as one JSON object holding the inputs, both
+/// orderings and the baseline distances, so the regions where the planar model breaks down
+/// can be collected across runs.
+pub struct DivergenceLog {
+    path: PathBuf,
+}
+
+impl DivergenceLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        DivergenceLog { path: path.into() }
+    }
+
+    /// Checks a decrypted "X is closer to Z than Y" result against the baseline and appends
+    /// the triple to the log if they disagree. Returns whether the result diverged.
+    pub fn check(
+        &self,
+        x: &Point,
+        y: &Point,
+        z: &Point,
+        fhe_x_closer: bool,
+    ) -> Result<bool, GpsFheError> {
+        let baseline_xz_km = haversine_km(x, z);
+        let baseline_yz_km = haversine_km(y, z);
+        let baseline_x_closer = baseline_xz_km < baseline_yz_km;
+        if baseline_x_closer == fhe_x_closer {
+            return Ok(false);
+        }
+
+        let entry = json!({
+            "x": x,
+            "y": y,
+            "z": z,
+            "fhe_x_closer": fhe_x_closer,
+            "baseline_x_closer": baseline_x_closer,
+            "baseline_xz_km": baseline_xz_km,
+            "baseline_yz_km": baseline_yz_km,
+        });
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| GpsFheError::Io(e.to_string()))?;
+        writeln!(file, "{}", entry).map_err(|e| GpsFheError::Io(e.to_string()))?;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Replays `compare_distances` in plaintext, wrapping like the 32-bit ciphertexts do.
+    fn encrypted_x_closer(x: &Point, y: &Point, z: &Point) -> bool {
+        let squared = |p: &Point| {
+            let (p_lat, p_lon) = p.scaled();
+            let (z_lat, z_lon) = z.scaled();
+            let d_lat = p_lat.wrapping_sub(z_lat);
+            let d_lon = p_lon.wrapping_sub(z_lon);
+            d_lat
+                .wrapping_mul(d_lat)
+                .wrapping_add(d_lon.wrapping_mul(d_lon))
+        };
+        squared(x) < squared(y)
+    }
+
+    #[test]
+    fn wrapped_comparisons_are_logged() {
+        let path = std::env::temp_dir().join(format!(
+            "tfhe-gps-distance-divergence-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let log = DivergenceLog::new(&path);
+
+        let zurich = Point::new(47.3769, 8.5417);
+        let basel = Point::new(47.5596, 7.5886);
+        let lugano = Point::new(46.0037, 8.9511);
+        // New York and Tokyo are so far from Zurich that their squared distances wrap and
+        // come out smaller than that of Paris
+        let paris = Point::new(48.8566, 2.3522);
+        let triples = [
+            (Point::new(40.7128, -74.006), paris, zurich),
+            (Point::new(35.6762, 139.6503), paris, zurich),
+            (basel, lugano, zurich),
+        ];
+
+        let diverged: Vec<bool> = triples
+            .iter()
+            .map(|(x, y, z)| log.check(x, y, z, encrypted_x_closer(x, y, z)).unwrap())
+            .collect();
+        assert_eq!(diverged, [true, true, false]);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        for (line, (x, _, _)) in lines.iter().zip(&triples) {
+            assert_eq!(line["x"]["lat"], x.lat);
+            assert_eq!(line["fhe_x_closer"], true);
+            assert_eq!(line["baseline_x_closer"], false);
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+}
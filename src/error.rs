@@ -0,0 +1,93 @@
+use std::fmt;
+
+/// Errors raised while preparing points or setting up a computation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GpsFheError {
+    /// The coordinate reference system is not one the crate can convert from.
+    UnsupportedCrs(&'static str),
+    /// Decimal-degree coordinates outside the valid latitude/longitude range.
+    OutOfRange { lat: f64, lon: f64 },
+    /// Coordinates that fit the expected region only with latitude and longitude exchanged.
+    LikelyLatLonSwapped { lat: f64, lon: f64 },
+    /// An NMEA sentence without a usable position.
+    InvalidNmea(String),
+    /// Candidate ids that do not refer to an available candidate.
+    MissingCandidates(Vec<usize>),
+    /// A query selected no candidates at all.
+    NoCandidatesSelected,
+    /// Public candidate ids that appear more than once.
+    DuplicateCandidateIds(Vec<u32>),
+    /// A result stream ended before delivering every declared result.
+    TruncatedResults { expected: u64, received: u64 },
+    /// A result stream could not be written or decoded.
+    MalformedResults(String),
+    /// Reading or writing a file or socket failed.
+    Io(String),
+    /// A value could not be encoded or decoded.
+    Serialization(String),
+    /// A reference-relative offset, in degrees, too large to encode without overflow.
+    RelativeOffsetTooLarge { d_lat: f64, d_lon: f64 },
+    /// A value could not be encrypted.
+    Encryption(String),
+    /// Serialized data written with a different `WIRE_VERSION`.
+    UnsupportedVersion { found: u16, supported: u16 },
+}
+
+impl fmt::Display for GpsFheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpsFheError::UnsupportedCrs(name) => {
+                write!(f, "unsupported coordinate reference system: {}", name)
+            }
+            GpsFheError::OutOfRange { lat, lon } => {
+                write!(
+                    f,
+                    "coordinate ({}, {}) is out of range for degrees",
+                    lat, lon
+                )?;
+                if lat.abs() > 90.0 {
+                    write!(f, "; the input may be in meters (e.g. Web Mercator)")?;
+                }
+                Ok(())
+            }
+            GpsFheError::LikelyLatLonSwapped { lat, lon } => write!(
+                f,
+                "coordinate ({}, {}) is outside the expected region; latitude and longitude may be swapped",
+                lat, lon
+            ),
+            GpsFheError::InvalidNmea(reason) => write!(f, "invalid NMEA sentence: {}", reason),
+            GpsFheError::MissingCandidates(ids) => {
+                write!(f, "unknown candidate ids: {:?}", ids)
+            }
+            GpsFheError::NoCandidatesSelected => write!(f, "no candidates selected"),
+            GpsFheError::DuplicateCandidateIds(ids) => {
+                write!(f, "duplicate candidate ids: {:?}", ids)
+            }
+            GpsFheError::TruncatedResults { expected, received } => {
+                write!(
+                    f,
+                    "result stream ended after {} of {} results",
+                    received, expected
+                )
+            }
+            GpsFheError::MalformedResults(reason) => {
+                write!(f, "malformed result stream: {}", reason)
+            }
+            GpsFheError::Io(reason) => write!(f, "I/O error: {}", reason),
+            GpsFheError::Serialization(reason) => write!(f, "serialization error: {}", reason),
+            GpsFheError::RelativeOffsetTooLarge { d_lat, d_lon } => write!(
+                f,
+                "offset ({}, {}) from the reference is too large for relative encoding",
+                d_lat, d_lon
+            ),
+            GpsFheError::Encryption(reason) => write!(f, "encryption failed: {}", reason),
+            GpsFheError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "serialized data has version {}, but only version {} is supported",
+                found, supported
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GpsFheError {}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use tfhe::prelude::*;
+use tfhe::FheBool;
+
+use crate::{scaled_radius_squared, squared_distance, ClientData};
+
+/// Encrypted geofence with separate enter and exit radii.
+///
+/// A single radius makes the inside bit flap while a point hovers around the boundary.
+/// Here a point must come within `enter_m` to be considered inside, and must move beyond
+/// `exit_m` to be considered outside again. The server cannot branch on the previous
+/// encrypted state, so each update selects between the two threshold checks with a cmux
+/// on the retained state ciphertext. The whole fence, state included, is serializable so
+/// it can be persisted between runs.
+///
+/// The thresholds are converted like the radius of `nearest_within`: they are ground meters
+/// on both axes only if the points are encoded with `DistanceMode::Planar` or `GroundMeters`;
+/// with `Degrees` they shrink east-west by `cos(latitude)`. Positions beyond the range of
+/// `squared_distance` from the centre can wrap and read as inside.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GeofenceState {
+    centre: ClientData,
+    enter_squared: u32,
+    exit_squared: u32,
+    inside: FheBool,
+}
+
+impl GeofenceState {
+    /// Creates a fence around `centre`, starting in the outside state.
+    ///
+    /// Panics if `enter_m` is larger than `exit_m`.
+    pub fn new(centre: ClientData, enter_m: u32, exit_m: u32) -> Self {
+        assert!(
+            enter_m <= exit_m,
+            "enter threshold must not exceed exit threshold"
+        );
+
+        GeofenceState {
+            centre,
+            enter_squared: scaled_radius_squared(enter_m as f64 / 1000.0),
+            exit_squared: scaled_radius_squared(exit_m as f64 / 1000.0),
+            inside: FheBool::encrypt_trivial(false),
+        }
+    }
+
+    /// Feeds a new position into the fence and returns the updated encrypted inside state.
+    pub fn update(&mut self, point: &ClientData) -> FheBool {
+        let distance = squared_distance(point, &self.centre);
+        let inside_enter = distance.le(self.enter_squared);
+        let inside_exit = distance.le(self.exit_squared);
+
+        // Once inside, only leaving the exit radius flips the state; otherwise we need the enter radius
+        self.inside = self.inside.if_then_else(&inside_exit, &inside_enter);
+        self.inside.clone()
+    }
+
+    /// Returns the current encrypted inside state without updating it.
+    pub fn state(&self) -> &FheBool {
+        &self.inside
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::{encrypt, keys};
+    use crate::SCALE_FACTOR;
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn state_changes_once_each_way_across_the_boundary() {
+        let client_key = keys();
+        let (lat, lon) = (47.3769, 8.5417);
+        let mut fence = GeofenceState::new(encrypt(lat, lon, &client_key), 450, 550);
+
+        // Steps due north in encoding units of about 11.1 m. The enter radius is 40 units
+        // (about 445 m) and the exit radius 49 units (about 545 m).
+        let walk = [60, 45, 38, 45, 49, 52, 45];
+        let states: Vec<bool> = walk
+            .iter()
+            .map(|&units| {
+                let point = encrypt(lat + units as f64 / SCALE_FACTOR, lon, &client_key);
+                fence.update(&point).decrypt(&client_key)
+            })
+            .collect();
+
+        assert_eq!(states, [false, false, true, true, true, false, false]);
+        let current: bool = fence.state().decrypt(&client_key);
+        assert!(!current);
+    }
+}
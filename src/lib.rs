@@ -0,0 +1,2354 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tfhe::prelude::*;
+use tfhe::{ClientKey, FheBool, FheUint32, FheUint64, FheUint8};
+use tfhe_versionable::Versionize;
+
+mod crs;
+mod divergence;
+mod error;
+mod geofence;
+mod nmea;
+mod stream;
+mod wire;
+
+pub use crs::{BoundingBox, Crs};
+pub use divergence::DivergenceLog;
+pub use error::GpsFheError;
+pub use geofence::GeofenceState;
+pub use nmea::point_from_nmea;
+pub use stream::{write_results, ResultStream};
+pub use wire::{
+    read_frame, write_frame, QueryPackage, MAX_CLIENT_DATA_LEN, MAX_FRAME_LEN, WIRE_VERSION,
+};
+
+/// Factor applied to decimal-degree coordinates before encryption (4 decimal places).
+pub const SCALE_FACTOR: f64 = 10_000.0;
+
+/// Offset added to latitudes before scaling so southern latitudes stay non-negative.
+pub const LAT_OFFSET_DEG: f64 = 90.0;
+
+/// Offset added to longitudes before scaling so western longitudes stay non-negative.
+pub const LON_OFFSET_DEG: f64 = 180.0;
+
+/// Bits needed for an encoded coordinate: `360 * SCALE_FACTOR` is just below `2^22`.
+pub const COORDINATE_BITS: u32 = 22;
+
+/// Approximate ground length of one degree of latitude, in kilometers.
+pub const KM_PER_DEGREE: f64 = 111.32;
+
+/// Mean Earth radius used by the plaintext great-circle baseline, in kilometers.
+pub const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// How the client maps coordinates onto the plane the encrypted math works in.
+///
+/// All points taking part in one computation must be encoded with the same mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DistanceMode {
+    /// Raw degrees: a degree of longitude counts as much as a degree of latitude.
+    Degrees,
+    /// Longitude is multiplied in plaintext by the cosine of a shared reference latitude, so
+    /// both axes measure ground distance. Costs nothing extra under encryption, but is only
+    /// valid over small extents around `reference_lat`, where the Earth is nearly flat.
+    Planar { reference_lat: f64 },
+    /// Each point's longitude offset from `central_lon` is multiplied by the cosine of that
+    /// point's own latitude (a sinusoidal projection), so east-west deltas measure ground
+    /// distance at every latitude rather than only near one reference. Suited to comparing
+    /// candidates at very different latitudes; distortion grows with the longitude distance
+    /// from `central_lon`, so pick it in the middle of the points.
+    GroundMeters { central_lon: f64 },
+}
+
+/// A plaintext GPS coordinate in decimal degrees.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Point {
+    pub fn new(lat: f64, lon: f64) -> Self {
+        Point { lat, lon }
+    }
+
+    /// Returns the offset and scaled latitude and longitude, as fed to the encrypted math.
+    ///
+    /// Latitude is shifted by `LAT_OFFSET_DEG` and longitude by `LON_OFFSET_DEG` before
+    /// scaling, so every valid coordinate maps to a non-negative `u32` (at most
+    /// `360 * SCALE_FACTOR`). Both points of a delta carry the same offset, so it cancels in
+    /// the subtraction and the distance math needs no correction.
+    ///
+    /// The encoding uses only an IEEE-754 addition, a multiplication and rounding to nearest,
+    /// all of which are exactly specified, so the same input yields the same integer on every
+    /// platform. Rounding rather than truncating also keeps inputs given to four decimals from
+    /// dropping a unit when the product lands just below the integer (7.5886° E scales to
+    /// 1875885.9999999998).
+    pub fn scaled(&self) -> (u32, u32) {
+        self.scaled_with_mode(DistanceMode::Degrees)
+    }
+
+    /// Returns the encoded latitude and longitude under `mode`.
+    ///
+    /// The latitude is always encoded as in `scaled`. Under `DistanceMode::Degrees` the
+    /// longitude is too, and the result is the same on every platform. `Planar` and
+    /// `GroundMeters` multiply the longitude by `f64::cos`, which is not correctly rounded
+    /// and may differ by an ulp between platforms; when the scaled product lands next to a
+    /// rounding boundary, the encoded longitude can then differ by one unit (about 11 m).
+    pub fn scaled_with_mode(&self, mode: DistanceMode) -> (u32, u32) {
+        let lon = match mode {
+            DistanceMode::Degrees => self.lon + LON_OFFSET_DEG,
+            DistanceMode::Planar { reference_lat } => {
+                (self.lon + LON_OFFSET_DEG) * reference_lat.to_radians().cos()
+            }
+            DistanceMode::GroundMeters { central_lon } => {
+                // Wrap the offset into [-180, 180) so points across the antimeridian stay close
+                let offset = (self.lon - central_lon + 180.0).rem_euclid(360.0) - 180.0;
+                offset * self.lat.to_radians().cos() + LON_OFFSET_DEG
+            }
+        };
+
+        (
+            ((self.lat + LAT_OFFSET_DEG) * SCALE_FACTOR).round() as u32,
+            (lon * SCALE_FACTOR).round() as u32,
+        )
+    }
+}
+
+/// Ground size of one encoding step at `latitude_deg`, in meters, as `(north_south,
+/// east_west)`.
+///
+/// One step is `1 / SCALE_FACTOR` degrees on either axis. A degree of longitude shrinks with
+/// the cosine of the latitude, so the east-west resolution gets finer towards the poles.
+/// This describes the default `DistanceMode::Degrees` encoding.
+pub fn quantization_resolution_m(latitude_deg: f64) -> (f64, f64) {
+    let ns_m = KM_PER_DEGREE * 1000.0 / SCALE_FACTOR;
+    let ew_m = ns_m * latitude_deg.to_radians().cos().abs();
+    (ns_m, ew_m)
+}
+
+/// Moves a point to the centre of its cell on a public grid of `cell_deg` degree squares.
+///
+/// Meant as client preprocessing before encryption: every point in a cell encrypts the
+/// same coordinates, so a result can no longer single out one user among the others sharing
+/// the cell. The price is accuracy: distances may be off by up to a half-diagonal of a cell
+/// per point, and comparisons between points in the same cell always tie. Cell centres are
+/// clamped to the valid coordinate range.
+///
+/// Panics unless `cell_deg` is positive and finite.
+pub fn snap_to_grid(point: &Point, cell_deg: f64) -> Point {
+    assert!(
+        cell_deg > 0.0 && cell_deg.is_finite(),
+        "grid cell size must be positive and finite"
+    );
+    let centre = |value: f64| ((value / cell_deg).floor() + 0.5) * cell_deg;
+    Point::new(
+        centre(point.lat).clamp(-90.0, 90.0),
+        centre(point.lon).clamp(-180.0, 180.0),
+    )
+}
+
+/// The demo trio used by the binary and benchmarks: Basel (X) and Lugano (Y), compared
+/// against Zurich (Z).
+pub fn default_demo_points() -> [(&'static str, Point); 3] {
+    [
+        ("Basel", Point::new(47.5596, 7.5886)),
+        ("Lugano", Point::new(46.0037, 8.9511)),
+        ("Zurich", Point::new(47.3769, 8.5417)),
+    ]
+}
+
+/// Plaintext great-circle distance between two points using the Haversine formula.
+///
+/// This is the ground truth the planar encrypted model is checked against.
+pub fn haversine_km(a: &Point, b: &Point) -> f64 {
+    let d_lat = (b.lat - a.lat).to_radians();
+    let d_lon = (b.lon - a.lon).to_radians();
+    let h = (d_lat / 2.0).sin().powi(2)
+        + a.lat.to_radians().cos() * b.lat.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+/// Encrypted, offset and scaled coordinates of a point as produced by the client.
+///
+/// See `Point::scaled` for the encoding.
+#[derive(Clone, Serialize, Deserialize, Versionize)]
+#[versionize(wire::ClientDataVersions)]
+pub struct ClientData {
+    pub lat: FheUint32,
+    pub lon: FheUint32,
+    /// Optional plaintext label. It travels with the ciphertexts and is visible to the server.
+    pub name: Option<String>,
+}
+
+impl ClientData {
+    /// Encodes the point with `Point::scaled` and encrypts both coordinates with the client key.
+    pub fn encrypt(point: &Point, client_key: &ClientKey) -> Result<Self, tfhe::Error> {
+        Self::encrypt_with_mode(point, DistanceMode::Degrees, client_key)
+    }
+
+    /// Encodes the point with `Point::scaled_with_mode` and encrypts it with the client key.
+    pub fn encrypt_with_mode(
+        point: &Point,
+        mode: DistanceMode,
+        client_key: &ClientKey,
+    ) -> Result<Self, tfhe::Error> {
+        let (lat, lon) = point.scaled_with_mode(mode);
+
+        Ok(ClientData {
+            lat: FheUint32::try_encrypt(lat, client_key)?,
+            lon: FheUint32::try_encrypt(lon, client_key)?,
+            name: None,
+        })
+    }
+
+    /// Encrypts every point with `encrypt_with_mode`, keeping their order.
+    ///
+    /// With the `parallel` feature the points are encrypted concurrently on the rayon thread
+    /// pool; encryption only reads the client key, so it can be shared across threads. Fails
+    /// with `GpsFheError::Encryption` if any point cannot be encrypted.
+    pub fn encrypt_batch(
+        points: &[Point],
+        mode: DistanceMode,
+        client_key: &ClientKey,
+    ) -> Result<Vec<Self>, GpsFheError> {
+        #[cfg(feature = "parallel")]
+        let points = points.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let points = points.iter();
+
+        points
+            .map(|point| {
+                Self::encrypt_with_mode(point, mode, client_key)
+                    .map_err(|e| GpsFheError::Encryption(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Like `encrypt`, but keeps only the top `precision_bits` of each `COORDINATE_BITS`-bit
+    /// encoded coordinate and zeroes the rest.
+    ///
+    /// The grid step becomes `2^(COORDINATE_BITS - precision_bits)` encoding units, about
+    /// 11 m times that power of two along a meridian (16 bits give roughly 710 m). The
+    /// coordinate is rounded down to the grid, so it can move by up to one step. Reduced
+    /// points stay on the same scale as full ones and can be compared with them; orderings
+    /// are preserved for points more than a couple of steps apart. The low bits are zeroed
+    /// before encryption, so any result decrypted from these points reveals at most the
+    /// coarser coordinates. Each ciphertext is still a full `FheUint32`, so nothing is saved
+    /// in size. The point is always encoded as with `DistanceMode::Degrees`. Panics unless
+    /// `precision_bits` is in `1..=COORDINATE_BITS`.
+    pub fn encrypt_with_precision(
+        point: &Point,
+        precision_bits: u32,
+        client_key: &ClientKey,
+    ) -> Result<Self, tfhe::Error> {
+        assert!(
+            (1..=COORDINATE_BITS).contains(&precision_bits),
+            "precision_bits must be between 1 and COORDINATE_BITS"
+        );
+
+        let mask = u32::MAX << (COORDINATE_BITS - precision_bits);
+        let (lat, lon) = point.scaled();
+
+        Ok(ClientData {
+            lat: FheUint32::try_encrypt(lat & mask, client_key)?,
+            lon: FheUint32::try_encrypt(lon & mask, client_key)?,
+            name: None,
+        })
+    }
+
+    /// Attaches a plaintext label to the encrypted point.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+}
+
+/// Trivially encrypts a public point on the server, without the client key.
+///
+/// The result is a regular `ClientData` and can be mixed freely with client-encrypted points
+/// in every distance function. Trivial ciphertexts carry no noise and hide nothing: anyone
+/// holding one can read the coordinates, so only use this for points that are public
+/// anyway, such as a published landmark. The point is encoded like `Point::scaled`, so only
+/// mix it with points encrypted under `DistanceMode::Degrees`. Must run on a thread with the
+/// server key set.
+pub fn trivial_point(lat: f64, lon: f64) -> ClientData {
+    let (lat, lon) = Point::new(lat, lon).scaled();
+
+    ClientData {
+        lat: FheUint32::encrypt_trivial(lat),
+        lon: FheUint32::encrypt_trivial(lon),
+        name: None,
+    }
+}
+
+/// Result of `compare_distances_with_reference_uncertainty` when X is clearly closer.
+pub const REFERENCE_CLOSER: u8 = 0;
+/// Result of `compare_distances_with_reference_uncertainty` when the ordering is ambiguous.
+pub const REFERENCE_AMBIGUOUS: u8 = 1;
+/// Result of `compare_distances_with_reference_uncertainty` when X is clearly farther.
+pub const REFERENCE_FARTHER: u8 = 2;
+
+/// A full turn of longitude in the `DistanceMode::Degrees` encoding.
+const FULL_TURN_SCALED: u32 = (360.0 * SCALE_FACTOR) as u32;
+
+/// Converts a kilometer distance into the scaled-degree units used by the encrypted math.
+fn scaled_km(km: f64) -> u64 {
+    (km / KM_PER_DEGREE * SCALE_FACTOR) as u64
+}
+
+/// Squares a kilometer radius in the scaled-degree units used by the encrypted math,
+/// saturating at `u32::MAX`.
+pub(crate) fn scaled_radius_squared(radius_km: f64) -> u32 {
+    let radius = scaled_km(radius_km);
+    (radius * radius).min(u32::MAX as u64) as u32
+}
+
+/// Integer square root of an encrypted value, using the bitwise digit-by-digit method.
+///
+/// Runs a fixed 16 rounds of compare-and-select, so the cost does not depend on the input.
+fn fhe_isqrt(value: &FheUint32) -> FheUint32 {
+    let mut remainder = value.clone();
+    let mut root = FheUint32::encrypt_trivial(0u32);
+    let mut bit = 1u32 << 30;
+
+    while bit != 0 {
+        let candidate = &root + bit;
+        let fits = remainder.ge(&candidate);
+        let shifted = &root >> 1u32;
+        remainder = fits.if_then_else(&(&remainder - &candidate), &remainder);
+        root = fits.if_then_else(&(&shifted + bit), &shifted);
+        bit >>= 2;
+    }
+
+    root
+}
+
+/// Absolute difference `|a - b|` of two encrypted values.
+///
+/// Selects between the two subtractions with an explicit comparison, so the result does not
+/// depend on which of them wraps. Equal inputs give zero.
+pub fn fhe_abs_diff(a: &FheUint32, b: &FheUint32) -> FheUint32 {
+    a.ge(b).if_then_else(&(a - b), &(b - a))
+}
+
+/// Squared Euclidean distance between two encrypted points: (lat1 - lat2)^2 + (lon1 - lon2)^2
+///
+/// The subtraction wraps on unsigned ciphertexts, which is harmless here because the square
+/// of the wrapped value is congruent to the square of the true delta.
+///
+/// The result only fits in 32 bits for points less than about 6.5° apart; beyond that the sum
+/// wraps and can come out near zero (6.5536° due north gives exactly 0), so comparisons go
+/// silently wrong. Every function built on this one shares the limit. Clients can check a
+/// pair in plaintext with `squared_distance_fits` before encrypting it, and the `_global`
+/// functions use `squared_distance_global` instead, which has no such limit.
+pub fn squared_distance(p1: &ClientData, p2: &ClientData) -> FheUint32 {
+    let d_lat = &p1.lat - &p2.lat;
+    let d_lon = &p1.lon - &p2.lon;
+    let d_lat2 = &d_lat * &d_lat;
+    let d_lon2 = &d_lon * &d_lon;
+    &d_lat2 + &d_lon2
+}
+
+/// Whether `squared_distance` between `a` and `b`, both encoded with `mode`, fits in 32 bits.
+///
+/// Runs on the plaintext points, so a client can reject a pair whose encrypted distance would
+/// wrap before encrypting anything.
+pub fn squared_distance_fits(a: &Point, b: &Point, mode: DistanceMode) -> bool {
+    let (a_lat, a_lon) = a.scaled_with_mode(mode);
+    let (b_lat, b_lon) = b.scaled_with_mode(mode);
+    let d_lat = a_lat.abs_diff(b_lat) as u64;
+    let d_lon = a_lon.abs_diff(b_lon) as u64;
+    d_lat * d_lat + d_lon * d_lon <= u32::MAX as u64
+}
+
+/// Like `squared_distance`, but takes the longitude delta the short way around the globe.
+///
+/// Points on either side of the antimeridian (179.9° E and 179.9° W) encode almost
+/// `360 * SCALE_FACTOR` apart, so `squared_distance` sees them as a full turn apart. Here
+/// the longitude delta is `min(|Δlon|, 360 * SCALE_FACTOR - |Δlon|)`, with `|Δlon|` taken by
+/// `fhe_abs_diff` rather than relying on wrapping. This costs a comparison, two
+/// selects and a minimum more than `squared_distance`, and only makes sense for points
+/// encoded with `DistanceMode::Degrees`: the other modes already rescale or recentre the
+/// longitude. The 32-bit limit on the result still applies, so it helps points a few
+/// degrees apart across the antimeridian, not antipodes.
+pub fn squared_distance_wrapped(p1: &ClientData, p2: &ClientData) -> FheUint32 {
+    let d_lat = &p1.lat - &p2.lat;
+    let d_lon = wrapped_lon_delta(p1, p2);
+    let d_lat2 = &d_lat * &d_lat;
+    let d_lon2 = &d_lon * &d_lon;
+    &d_lat2 + &d_lon2
+}
+
+/// Longitude delta the short way around the globe, `min(|Δlon|, 360 * SCALE_FACTOR - |Δlon|)`.
+fn wrapped_lon_delta(p1: &ClientData, p2: &ClientData) -> FheUint32 {
+    let direct = fhe_abs_diff(&p1.lon, &p2.lon);
+    direct.min(&(FULL_TURN_SCALED - &direct))
+}
+
+/// Right shift applied to each delta by `squared_distance_global`.
+///
+/// The largest deltas are 180° of latitude and, after wrapping, 180° of longitude, each
+/// `180 * SCALE_FACTOR` units. Shifted right by 6, the sum of their squares is about
+/// `1.6 * 10^9`, which fits in a `u32`.
+pub const GLOBAL_DELTA_SHIFT: u32 = 6;
+
+/// Squared distance that cannot overflow for any pair of points on the globe.
+///
+/// `squared_distance` only fits in 32 bits for points a few degrees apart, and beyond that
+/// its result wraps and comparisons between far-apart points can come out in the wrong
+/// order. Here each absolute delta is shifted right by `GLOBAL_DELTA_SHIFT` before squaring,
+/// with the longitude taken the short way around the antimeridian. The result is in units of
+/// `2^GLOBAL_DELTA_SHIFT` scaled degrees (about 710 m), squared, so it can only be compared
+/// with other results of this function. Only meaningful for `DistanceMode::Degrees` points.
+pub fn squared_distance_global(p1: &ClientData, p2: &ClientData) -> FheUint32 {
+    let d_lat = fhe_abs_diff(&p1.lat, &p2.lat) >> GLOBAL_DELTA_SHIFT;
+    let d_lon = wrapped_lon_delta(p1, p2) >> GLOBAL_DELTA_SHIFT;
+    let d_lat2 = &d_lat * &d_lat;
+    let d_lon2 = &d_lon * &d_lon;
+    &d_lat2 + &d_lon2
+}
+
+/// Like `scaled_radius_squared`, but in the shifted units of `squared_distance_global`.
+fn global_radius_squared(radius_km: f64) -> u32 {
+    let radius = scaled_km(radius_km) >> GLOBAL_DELTA_SHIFT;
+    (radius * radius).min(u32::MAX as u64) as u32
+}
+
+/// Squared and linear distance between two encrypted points, as `(d², d)`.
+///
+/// The linear form is the integer square root of the squared one, in scaled degrees, and is
+/// meant for display. Comparisons should use the squared form: it is exact and skips the 16
+/// rounds of the square root.
+pub fn distance_both(p1: &ClientData, p2: &ClientData) -> (FheUint32, FheUint32) {
+    let squared = squared_distance(p1, p2);
+    let linear = fhe_isqrt(&squared);
+    (squared, linear)
+}
+
+/// Decrypts a squared distance from `squared_distance` and converts it to kilometers.
+///
+/// The conversion undoes the squaring and `SCALE_FACTOR` and applies `KM_PER_DEGREE`, so it
+/// is only as good as the encoding. With `DistanceMode::Degrees` east-west deltas are
+/// overstated by `1 / cos(latitude)`: Basel to Zurich comes out at about 108 km against
+/// 74 km on the great circle. `Planar` and `GroundMeters` points stay within a few percent
+/// of `haversine_km` over the extents they are meant for. Rounding to the encoding grid adds
+/// about 11 m per coordinate.
+pub fn decrypt_distance_km(distance: &FheUint32, client_key: &ClientKey) -> f64 {
+    let squared: u32 = distance.decrypt(client_key);
+    (squared as f64).sqrt() / SCALE_FACTOR * KM_PER_DEGREE
+}
+
+/// Encrypted squared distance that keeps track of its own encoding.
+///
+/// Unlike a bare `FheUint32` from `squared_distance`, it can only be read back as
+/// kilometers, so callers cannot forget to undo the squaring and scaling.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EncryptedDistance(FheUint32);
+
+impl EncryptedDistance {
+    /// The underlying squared distance in scaled units, for further encrypted computation.
+    pub fn squared(&self) -> &FheUint32 {
+        &self.0
+    }
+
+    /// Decrypts the distance in kilometers. See `decrypt_distance_km` for the error envelope.
+    pub fn decrypt_km(&self, client_key: &ClientKey) -> f64 {
+        decrypt_distance_km(&self.0, client_key)
+    }
+}
+
+/// Computes the encrypted distance between two points, decodable with
+/// `EncryptedDistance::decrypt_km`.
+pub fn encrypted_distance(p1: &ClientData, p2: &ClientData) -> EncryptedDistance {
+    EncryptedDistance(squared_distance(p1, p2))
+}
+
+/// Returns an encrypted `true` if X is closer to Z than Y is.
+///
+/// Subject to the range limit of `squared_distance`; see `compare_distances_global` for
+/// points farther apart.
+pub fn compare_distances(x: &ClientData, y: &ClientData, z: &ClientData) -> FheBool {
+    let distance_xz = squared_distance(x, z);
+    let distance_yz = squared_distance(y, z);
+    distance_xz.lt(&distance_yz)
+}
+
+/// Like `compare_distances`, but through `squared_distance_global`, so the answer stays
+/// correct for points anywhere on the globe, at about 710 m resolution.
+pub fn compare_distances_global(x: &ClientData, y: &ClientData, z: &ClientData) -> FheBool {
+    let distance_xz = squared_distance_global(x, z);
+    let distance_yz = squared_distance_global(y, z);
+    distance_xz.lt(&distance_yz)
+}
+
+/// Squared distance between an encrypted point and a public one, using scalar operations.
+///
+/// The public point is encoded with `Point::scaled`, so the encrypted one must use
+/// `DistanceMode::Degrees` too; against `Planar` or `GroundMeters` data the longitudes are on
+/// different scales and the result is meaningless.
+pub fn squared_distance_to_public(point: &ClientData, public: &Point) -> FheUint32 {
+    let (lat, lon) = public.scaled();
+    let d_lat = &point.lat - lat;
+    let d_lon = &point.lon - lon;
+    let d_lat2 = &d_lat * &d_lat;
+    let d_lon2 = &d_lon * &d_lon;
+    &d_lat2 + &d_lon2
+}
+
+/// Folds an encrypted argmin over `(index, distance)` pairs.
+///
+/// Returns the minimum distance and its encrypted index, or `None` when there are no
+/// pairs. Ties resolve to the earliest pair.
+fn argmin(mut distances: impl Iterator<Item = (u32, FheUint32)>) -> Option<(FheUint32, FheUint32)> {
+    let (first_index, mut best_distance) = distances.next()?;
+    let mut best_index = FheUint32::encrypt_trivial(first_index);
+
+    for (i, distance) in distances {
+        let closer = distance.lt(&best_distance);
+        let index = FheUint32::encrypt_trivial(i);
+        best_distance = closer.if_then_else(&distance, &best_distance);
+        best_index = closer.if_then_else(&index, &best_index);
+    }
+
+    Some((best_distance, best_index))
+}
+
+/// A squared distance between two encrypted points, either `squared_distance` or
+/// `squared_distance_global`.
+type SquaredDistanceFn = fn(&ClientData, &ClientData) -> FheUint32;
+
+/// Folds an encrypted argmin over candidate distances to `reference`.
+fn nearest_distance(
+    candidates: &[ClientData],
+    reference: &ClientData,
+    distance: SquaredDistanceFn,
+) -> Option<(FheUint32, FheUint32)> {
+    argmin(
+        candidates
+            .iter()
+            .enumerate()
+            .map(|(i, candidate)| (i as u32, distance(candidate, reference))),
+    )
+}
+
+/// Returns the encrypted index of the candidate nearest to `reference`.
+///
+/// Ties resolve to the lowest index. Candidates beyond the range of `squared_distance` can
+/// wrap to a small distance and win; use `find_nearest_global` for those. Panics if
+/// `candidates` is empty.
+pub fn find_nearest(candidates: &[ClientData], reference: &ClientData) -> FheUint32 {
+    let (index, _) = find_nearest_with_distance(candidates, reference);
+    index
+}
+
+/// Like `find_nearest`, but through `squared_distance_global`, so candidates anywhere on the
+/// globe are ranked correctly, at about 710 m resolution.
+pub fn find_nearest_global(candidates: &[ClientData], reference: &ClientData) -> FheUint32 {
+    let (_, index) = nearest_distance(candidates, reference, squared_distance_global)
+        .expect("nearest requires at least one candidate");
+    index
+}
+
+/// Returns the encrypted index of the nearest candidate together with its squared distance.
+///
+/// The minimum distance is carried through the same argmin fold, so callers that need it
+/// for thresholding avoid a second pass. Ties resolve to the lowest index. Panics if
+/// `candidates` is empty.
+pub fn find_nearest_with_distance(
+    candidates: &[ClientData],
+    reference: &ClientData,
+) -> (FheUint32, FheUint32) {
+    let (distance, index) = nearest_distance(candidates, reference, squared_distance)
+        .expect("nearest requires at least one candidate");
+    (index, distance)
+}
+
+/// Finds the nearest candidate to `reference` and whether it lies within `radius_km`.
+///
+/// Returns an encrypted flag telling whether any candidate is inside the radius, and the
+/// encrypted index of the nearest such candidate. The index is zero when the flag is false.
+/// Ties resolve to the lowest index. The radius is public.
+///
+/// The radius is converted to scaled degrees with `KM_PER_DEGREE`, which only measures ground
+/// kilometers along a meridian. Under the default `DistanceMode::Degrees` encoding a degree of
+/// longitude counts as much as one of latitude, so east-west the radius covers only
+/// `cos(latitude)` of its length: 200 km at 47° N reaches about 136 km east or west. Encode
+/// the points with `DistanceMode::Planar` or `GroundMeters` for a radius in ground kilometers
+/// on both axes. Candidates beyond the range of `squared_distance` can also wrap into any
+/// radius; `nearest_within_global` does not have that problem.
+pub fn nearest_within(
+    candidates: &[ClientData],
+    reference: &ClientData,
+    radius_km: u32,
+) -> (FheBool, FheUint32) {
+    let radius_squared = scaled_radius_squared(radius_km as f64);
+    nearest_within_by(candidates, reference, radius_squared, squared_distance)
+}
+
+/// Like `nearest_within`, but through `squared_distance_global`, so far-away candidates can
+/// never wrap into the radius. The radius is rounded down to the 710 m grid of that
+/// function.
+pub fn nearest_within_global(
+    candidates: &[ClientData],
+    reference: &ClientData,
+    radius_km: u32,
+) -> (FheBool, FheUint32) {
+    let radius_squared = global_radius_squared(radius_km as f64);
+    nearest_within_by(
+        candidates,
+        reference,
+        radius_squared,
+        squared_distance_global,
+    )
+}
+
+fn nearest_within_by(
+    candidates: &[ClientData],
+    reference: &ClientData,
+    radius_squared: u32,
+    distance: SquaredDistanceFn,
+) -> (FheBool, FheUint32) {
+    let zero = FheUint32::encrypt_trivial(0u32);
+    let Some((best_distance, best_index)) = nearest_distance(candidates, reference, distance)
+    else {
+        return (FheBool::encrypt_trivial(false), zero);
+    };
+
+    // The nearest candidate is inside the radius iff any candidate is
+    let found = best_distance.le(radius_squared);
+    let index = found.if_then_else(&best_index, &zero);
+
+    (found, index)
+}
+
+/// Compares two distances measured against different references.
+///
+/// Returns an encrypted `true` if `a` is closer to `p` than `b` is to `q`. Both distances
+/// go through `squared_distance`, so they share the same scale and are directly comparable.
+pub fn compare_cross_distances(
+    a: &ClientData,
+    p: &ClientData,
+    b: &ClientData,
+    q: &ClientData,
+) -> FheBool {
+    let distance_ap = squared_distance(a, p);
+    let distance_bq = squared_distance(b, q);
+    distance_ap.lt(&distance_bq)
+}
+
+/// Interpolates between two encrypted points at the public fraction `numerator / denominator`.
+///
+/// This is a linear interpolation, not a spherical one. It follows the same planar model as
+/// `squared_distance`: it walks the straight line between the scaled coordinates rather than
+/// the great circle. Over the short
+/// distances this crate handles the two are close, but paths crossing the antimeridian go
+/// the long way round. Antipodal points have no single shortest great circle between them,
+/// so there is no great-circle path to follow; for them this still returns a point on the
+/// straight line. Each encoded coordinate, up to `360 * SCALE_FACTOR`, is multiplied by up
+/// to `denominator` before the division, so the denominator may be at most
+/// `u32::MAX / (360 * SCALE_FACTOR)`, which is 1193.
+///
+/// Panics if `denominator` is zero, larger than that bound, or smaller than `numerator`.
+pub fn interpolate_planar(
+    a: &ClientData,
+    b: &ClientData,
+    fraction_numerator: u32,
+    fraction_denominator: u32,
+) -> ClientData {
+    assert!(
+        fraction_denominator > 0,
+        "fraction denominator must be non-zero"
+    );
+    assert!(
+        fraction_denominator <= u32::MAX / FULL_TURN_SCALED,
+        "fraction denominator is too large to interpolate within 32 bits"
+    );
+    assert!(
+        fraction_numerator <= fraction_denominator,
+        "fraction must be within [0, 1]"
+    );
+
+    // Weighted sum avoids the wrapping subtraction of (b - a) on unsigned ciphertexts
+    let weight_a = fraction_denominator - fraction_numerator;
+    let weight_b = fraction_numerator;
+    let lat = (&a.lat * weight_a + &b.lat * weight_b) / fraction_denominator;
+    let lon = (&a.lon * weight_a + &b.lon * weight_b) / fraction_denominator;
+
+    ClientData {
+        lat,
+        lon,
+        name: None,
+    }
+}
+
+/// Compares the distances from X and Y to a reference Z whose position is only known to
+/// within `z_error_km`.
+///
+/// Moving Z by up to `z_error_km` can change each distance by that much, so the ordering is
+/// only trusted when the two distances differ by more than `2 * z_error_km`. Returns an
+/// encrypted `REFERENCE_CLOSER`, `REFERENCE_AMBIGUOUS` or `REFERENCE_FARTHER` describing X
+/// relative to Y. The error radius is public. Requires an encrypted square root per distance,
+/// which makes this considerably more expensive than a plain comparison.
+///
+/// Like the radius of `nearest_within`, the error radius is converted along a meridian, so
+/// with `Degrees` points it understates the allowed east-west error by `cos(latitude)`. X and
+/// Y must lie within the range of `squared_distance` from Z.
+pub fn compare_distances_with_reference_uncertainty(
+    x: &ClientData,
+    y: &ClientData,
+    z: &ClientData,
+    z_error_km: u32,
+) -> FheUint8 {
+    let distance_x = fhe_isqrt(&squared_distance(x, z));
+    let distance_y = fhe_isqrt(&squared_distance(y, z));
+    let margin = (2 * scaled_km(z_error_km as f64)).min(u16::MAX as u64) as u32;
+
+    let closer = (&distance_x + margin).lt(&distance_y);
+    let farther = (&distance_y + margin).lt(&distance_x);
+
+    let closer_value = FheUint8::encrypt_trivial(REFERENCE_CLOSER);
+    let ambiguous_value = FheUint8::encrypt_trivial(REFERENCE_AMBIGUOUS);
+    let farther_value = FheUint8::encrypt_trivial(REFERENCE_FARTHER);
+    let otherwise = farther.if_then_else(&farther_value, &ambiguous_value);
+    closer.if_then_else(&closer_value, &otherwise)
+}
+
+/// Computes the encrypted bounding box of a set of points.
+///
+/// Returns `(min_lat, max_lat, min_lon, max_lon)` in the same scaled encoding as
+/// `ClientData`, folded with homomorphic min/max so the server learns nothing about
+/// individual points.
+///
+/// Panics if `points` is empty.
+pub fn encrypted_extent(points: &[ClientData]) -> (FheUint32, FheUint32, FheUint32, FheUint32) {
+    let (first, rest) = points
+        .split_first()
+        .expect("extent requires at least one point");
+
+    let mut min_lat = first.lat.clone();
+    let mut max_lat = first.lat.clone();
+    let mut min_lon = first.lon.clone();
+    let mut max_lon = first.lon.clone();
+    for point in rest {
+        min_lat = min_lat.min(&point.lat);
+        max_lat = max_lat.max(&point.lat);
+        min_lon = min_lon.min(&point.lon);
+        max_lon = max_lon.max(&point.lon);
+    }
+
+    (min_lat, max_lat, min_lon, max_lon)
+}
+
+/// Compares two public candidates against an encrypted reference.
+///
+/// Returns an encrypted `true` if `x` is closer to `reference` than `y`. Only the reference
+/// is sensitive here, so the candidate coordinates enter as plaintext scalars, which is
+/// cheaper than comparing fully encrypted points. The reference must be encoded with
+/// `DistanceMode::Degrees`; see `squared_distance_to_public`.
+pub fn compare_public_candidates(x: &Point, y: &Point, reference: &ClientData) -> FheBool {
+    let distance_x = squared_distance_to_public(reference, x);
+    let distance_y = squared_distance_to_public(reference, y);
+    distance_x.lt(&distance_y)
+}
+
+/// Counts how many points fall in each ring around `reference`.
+///
+/// `ring_edges_km` lists the public outer radius of each ring in ascending order; the first
+/// ring is the disc up to the first edge and each later ring covers the annulus between
+/// consecutive edges. Each count is the difference between two cumulative within-radius
+/// counts, so every point's distance is computed only once.
+///
+/// Edges are converted like the radius of `nearest_within` and share its limits: they are
+/// ground kilometers north-south only, unless the points use `Planar` or `GroundMeters`, and
+/// points beyond the range of `squared_distance` can wrap into an inner ring (use
+/// `ring_counts_global`).
+///
+/// Panics if the edges are not in ascending order.
+pub fn ring_counts(
+    points: &[ClientData],
+    reference: &ClientData,
+    ring_edges_km: &[u32],
+) -> Vec<FheUint32> {
+    ring_counts_by(
+        points,
+        reference,
+        ring_edges_km,
+        squared_distance,
+        scaled_radius_squared,
+    )
+}
+
+/// Like `ring_counts`, but through `squared_distance_global`, so far-away points always land
+/// in the outer rings. Edges are rounded down to the 710 m grid of that function.
+pub fn ring_counts_global(
+    points: &[ClientData],
+    reference: &ClientData,
+    ring_edges_km: &[u32],
+) -> Vec<FheUint32> {
+    ring_counts_by(
+        points,
+        reference,
+        ring_edges_km,
+        squared_distance_global,
+        global_radius_squared,
+    )
+}
+
+fn ring_counts_by(
+    points: &[ClientData],
+    reference: &ClientData,
+    ring_edges_km: &[u32],
+    distance: SquaredDistanceFn,
+    radius_squared: fn(f64) -> u32,
+) -> Vec<FheUint32> {
+    assert!(
+        ring_edges_km.windows(2).all(|pair| pair[0] <= pair[1]),
+        "ring edges must be in ascending order"
+    );
+
+    let distances: Vec<FheUint32> = points
+        .iter()
+        .map(|point| distance(point, reference))
+        .collect();
+
+    let mut counts = Vec::with_capacity(ring_edges_km.len());
+    let mut previous = FheUint32::encrypt_trivial(0u32);
+    for &edge_km in ring_edges_km {
+        let radius_squared = radius_squared(edge_km as f64);
+        let mut cumulative = FheUint32::encrypt_trivial(0u32);
+        for distance in &distances {
+            cumulative += FheUint32::cast_from(distance.le(radius_squared));
+        }
+        counts.push(&cumulative - &previous);
+        previous = cumulative;
+    }
+
+    counts
+}
+
+/// Marks which points to keep when removing near-duplicates.
+///
+/// Walks the points in order and returns, per point, an encrypted flag that is true iff it
+/// is not within `tolerance_m` of an earlier kept point, so the first point of each cluster
+/// represents it. The tolerance is public. Costs O(n²) distance computations.
+///
+/// The tolerance is converted like the radius of `nearest_within`, so with `Degrees` points
+/// it only holds north-south, and two points beyond the range of `squared_distance` can wrap
+/// and be taken for duplicates.
+pub fn deduplicate(points: &[ClientData], tolerance_m: u32) -> Vec<FheBool> {
+    let tolerance_squared = scaled_radius_squared(tolerance_m as f64 / 1000.0);
+
+    let mut keep: Vec<FheBool> = Vec::with_capacity(points.len());
+    for (i, point) in points.iter().enumerate() {
+        let mut duplicate = FheBool::encrypt_trivial(false);
+        for (earlier, kept) in points[..i].iter().zip(&keep) {
+            let near = squared_distance(point, earlier).le(tolerance_squared);
+            duplicate |= near & kept;
+        }
+        keep.push(!duplicate);
+    }
+
+    keep
+}
+
+/// Returns the encrypted index of the nearest candidate among the selected `ids`.
+///
+/// The selection is plaintext, so the server learns which candidates take part in the
+/// query; use `find_nearest_masked` to hide it. The returned index refers to the position
+/// in `candidates`. Fails with `GpsFheError::NoCandidatesSelected` if `ids` is empty, and
+/// with `GpsFheError::MissingCandidates` listing every id that is out of range.
+pub fn find_nearest_among(
+    candidates: &[ClientData],
+    reference: &ClientData,
+    ids: &[usize],
+) -> Result<FheUint32, GpsFheError> {
+    if ids.is_empty() {
+        return Err(GpsFheError::NoCandidatesSelected);
+    }
+    let missing: Vec<usize> = ids
+        .iter()
+        .copied()
+        .filter(|&id| id >= candidates.len())
+        .collect();
+    if !missing.is_empty() {
+        return Err(GpsFheError::MissingCandidates(missing));
+    }
+
+    let distances = ids
+        .iter()
+        .map(|&id| (id as u32, squared_distance(&candidates[id], reference)));
+    let (_, index) = argmin(distances).expect("ids is not empty");
+
+    Ok(index)
+}
+
+/// Returns the encrypted index of the nearest candidate whose encrypted `mask` bit is set.
+///
+/// Excluded candidates are not skipped but have their distance replaced with `u32::MAX`,
+/// so the server pays for every candidate and learns nothing about the selection. If no
+/// candidate is selected the result is index zero.
+///
+/// Panics if `candidates` is empty or `mask` has a different length.
+pub fn find_nearest_masked(
+    candidates: &[ClientData],
+    reference: &ClientData,
+    mask: &[FheBool],
+) -> FheUint32 {
+    assert_eq!(
+        candidates.len(),
+        mask.len(),
+        "mask must have one bit per candidate"
+    );
+
+    let excluded = FheUint32::encrypt_trivial(u32::MAX);
+    let distances = candidates
+        .iter()
+        .zip(mask)
+        .enumerate()
+        .map(|(i, (candidate, selected))| {
+            let distance = squared_distance(candidate, reference);
+            (i as u32, selected.if_then_else(&distance, &excluded))
+        });
+    let (_, index) = argmin(distances).expect("nearest requires at least one candidate");
+
+    index
+}
+
+/// Compares the distances from X and Y to Z after adding a client-supplied encrypted mask to
+/// both.
+///
+/// Adding the same offset to both terms leaves their ordering unchanged, but a masked term
+/// that leaks through a bug or a log is meaningless without the mask. The sums are taken in
+/// `FheUint64`, so the client may draw the mask uniformly from the whole `u32` range and
+/// neither masked term can wrap. The distances themselves are subject to the range limit of
+/// `squared_distance`. Returns an encrypted `true` if X is closer to Z than Y.
+pub fn compare_distances_masked(
+    x: &ClientData,
+    y: &ClientData,
+    z: &ClientData,
+    mask: &FheUint32,
+) -> FheBool {
+    let mask: FheUint64 = mask.clone().cast_into();
+    let distance_xz: FheUint64 = squared_distance(x, z).cast_into();
+    let distance_yz: FheUint64 = squared_distance(y, z).cast_into();
+    (distance_xz + &mask).lt(&(distance_yz + &mask))
+}
+
+/// Computes the encrypted squared distance from `point` to each landmark, paired with the
+/// landmark's name.
+pub fn distances_to_named(
+    point: &ClientData,
+    landmarks: &[ClientData],
+) -> Vec<(Option<String>, FheUint32)> {
+    landmarks
+        .iter()
+        .map(|landmark| (landmark.name.clone(), squared_distance(point, landmark)))
+        .collect()
+}
+
+/// Encrypted offsets of a candidate from a reference point known at precompute time.
+///
+/// The client encodes `(lat - ref_lat, (lon - ref_lon) * cos(ref_lat))` directly, so the
+/// server compares distances to the implicit reference without any subtraction, and both
+/// axes measure ground distance near the reference. Offsets use
+/// `RELATIVE_SCALE_FACTOR` and are stored in two's complement; squaring is exact modulo
+/// `2^32`, so the sign does not matter. Relative and absolute data are separate types
+/// and cannot be mixed in one comparison.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RelativeClientData {
+    pub d_lat: FheUint32,
+    pub d_lon: FheUint32,
+}
+
+/// Factor applied to reference-relative offsets before encryption.
+pub const RELATIVE_SCALE_FACTOR: f64 = 15_000.0;
+
+/// Largest offset per axis, in degrees of latitude (about 334 km), that `precompute_relative`
+/// accepts. At
+/// `RELATIVE_SCALE_FACTOR` two such offsets still square and sum within 32 bits.
+pub const MAX_RELATIVE_OFFSET_DEG: f64 = 3.0;
+
+/// Encrypts `candidate` as its offset from `reference`.
+///
+/// The longitude offset is scaled by the cosine of the reference latitude before the range
+/// check, so the limit applies to ground distance on both axes. Fails with
+/// `GpsFheError::RelativeOffsetTooLarge` if either offset exceeds `MAX_RELATIVE_OFFSET_DEG`,
+/// which would overflow the squared distance, and with `GpsFheError::Encryption` if
+/// encryption fails.
+pub fn precompute_relative(
+    candidate: &Point,
+    reference: &Point,
+    client_key: &ClientKey,
+) -> Result<RelativeClientData, GpsFheError> {
+    let d_lat = candidate.lat - reference.lat;
+    let d_lon = (candidate.lon - reference.lon) * reference.lat.to_radians().cos();
+    if d_lat.abs() > MAX_RELATIVE_OFFSET_DEG || d_lon.abs() > MAX_RELATIVE_OFFSET_DEG {
+        return Err(GpsFheError::RelativeOffsetTooLarge { d_lat, d_lon });
+    }
+
+    let encrypt = |delta: f64| {
+        let encoded = (delta * RELATIVE_SCALE_FACTOR).round() as i32 as u32;
+        FheUint32::try_encrypt(encoded, client_key)
+            .map_err(|e| GpsFheError::Encryption(e.to_string()))
+    };
+    Ok(RelativeClientData {
+        d_lat: encrypt(d_lat)?,
+        d_lon: encrypt(d_lon)?,
+    })
+}
+
+/// Compares two candidates encoded relative to the same reference.
+///
+/// Returns an encrypted `true` if `x` is closer to the reference than `y`.
+pub fn compare_relative_distances(x: &RelativeClientData, y: &RelativeClientData) -> FheBool {
+    let distance_x = &(&x.d_lat * &x.d_lat) + &(&x.d_lon * &x.d_lon);
+    let distance_y = &(&y.d_lat * &y.d_lat) + &(&y.d_lon * &y.d_lon);
+    distance_x.lt(&distance_y)
+}
+
+/// Groups points by single-linkage clustering and returns an encrypted cluster id per point.
+///
+/// Two points are linked when they lie within the public `link_distance_km` of each other,
+/// and a cluster is a connected set of links. Each point ends up with the smallest index in
+/// its cluster, found by propagating the minimum label along links for `n - 1` rounds,
+/// enough to cross any cluster. This costs O(n²) distance computations plus O(n³)
+/// encrypted selects, so it is only practical for small sets.
+///
+/// The link distance has the same limits as the radius of `nearest_within`: it is ground
+/// kilometers north-south only with `Degrees` points, and points beyond the range of
+/// `squared_distance` can wrap into a spurious link.
+pub fn cluster(points: &[ClientData], link_distance_km: u32) -> Vec<FheUint32> {
+    let link_squared = scaled_radius_squared(link_distance_km as f64);
+    let n = points.len();
+
+    // links[i][j] for j < i; the relation is symmetric
+    let links: Vec<Vec<FheBool>> = (0..n)
+        .map(|i| {
+            (0..i)
+                .map(|j| squared_distance(&points[i], &points[j]).le(link_squared))
+                .collect()
+        })
+        .collect();
+    let link = |i: usize, j: usize| if j < i { &links[i][j] } else { &links[j][i] };
+
+    let unlinked = FheUint32::encrypt_trivial(u32::MAX);
+    let mut labels: Vec<FheUint32> = (0..n as u32).map(FheUint32::encrypt_trivial).collect();
+    for _ in 1..n {
+        labels = (0..n)
+            .map(|i| {
+                (0..n)
+                    .filter(|&j| j != i)
+                    .fold(labels[i].clone(), |label, j| {
+                        label.min(&link(i, j).if_then_else(&labels[j], &unlinked))
+                    })
+            })
+            .collect();
+    }
+
+    labels
+}
+
+/// Returns an encrypted `true` if `point` is nearer to `a` than to `b`.
+///
+/// Unlike a comparison against a fixed reference, this fixes the candidate and asks which
+/// of two landmarks it is closer to.
+pub fn nearer_to(point: &ClientData, a: &ClientData, b: &ClientData) -> FheBool {
+    let distance_a = squared_distance(point, a);
+    let distance_b = squared_distance(point, b);
+    distance_a.lt(&distance_b)
+}
+
+/// Computes the weighted mean position `Σ wᵢ·pᵢ / Σ wᵢ` of encrypted points with public
+/// integer weights.
+///
+/// Like `interpolate_planar`, this averages the encoded coordinates on the plane, so sets
+/// that straddle the antimeridian average to the wrong side of the globe. Encoded
+/// coordinates go up to `360 * SCALE_FACTOR`, so the weights may sum to at most
+/// `u32::MAX / (360 * SCALE_FACTOR)`, which is 1193, to stay within 32 bits.
+///
+/// Panics if `points` and `weights` differ in length, or the weights sum to zero or to more
+/// than that bound.
+pub fn weighted_centroid(points: &[ClientData], weights: &[u32]) -> ClientData {
+    assert_eq!(points.len(), weights.len(), "need one weight per point");
+    let total = weights
+        .iter()
+        .try_fold(0u32, |sum, &weight| sum.checked_add(weight))
+        .filter(|&total| total <= u32::MAX / FULL_TURN_SCALED)
+        .expect("weights are too large to average within 32 bits");
+    assert!(total > 0, "weights must not sum to zero");
+
+    let mut lat = FheUint32::encrypt_trivial(0u32);
+    let mut lon = FheUint32::encrypt_trivial(0u32);
+    for (point, &weight) in points.iter().zip(weights) {
+        lat += &point.lat * weight;
+        lon += &point.lon * weight;
+    }
+
+    ClientData {
+        lat: lat / total,
+        lon: lon / total,
+        name: None,
+    }
+}
+
+/// Returns the encrypted public ids of the `k` candidates nearest to `reference`, nearest
+/// first.
+///
+/// Each round runs `argmin` over the remaining distances, indexed by public id, then pushes
+/// the winner's distance to `u32::MAX` so the next round skips it. The client only has to
+/// decrypt `k` small ciphertexts. Ties resolve to the earlier candidate. When `k` exceeds the
+/// number of candidates, every candidate is returned, so the result is never longer than
+/// `candidates`.
+///
+/// The winner of each round is found again by its id, so ids must be distinct. They are
+/// public and checked in plaintext: any id that appears more than once is reported in
+/// `GpsFheError::DuplicateCandidateIds` before anything is computed.
+pub fn nearest_public_ids(
+    candidates: &[(u32, ClientData)],
+    reference: &ClientData,
+    k: usize,
+) -> Result<Vec<FheUint32>, GpsFheError> {
+    let mut ids: Vec<u32> = candidates.iter().map(|(id, _)| *id).collect();
+    ids.sort_unstable();
+    let mut duplicates: Vec<u32> = ids
+        .windows(2)
+        .filter(|pair| pair[0] == pair[1])
+        .map(|pair| pair[0])
+        .collect();
+    duplicates.dedup();
+    if !duplicates.is_empty() {
+        return Err(GpsFheError::DuplicateCandidateIds(duplicates));
+    }
+
+    let mut distances: Vec<FheUint32> = candidates
+        .iter()
+        .map(|(_, candidate)| squared_distance(candidate, reference))
+        .collect();
+    let taken = FheUint32::encrypt_trivial(u32::MAX);
+
+    let mut shortlist = Vec::with_capacity(k.min(candidates.len()));
+    for _ in 0..k.min(candidates.len()) {
+        let Some((_, best_id)) = argmin(
+            candidates
+                .iter()
+                .map(|(id, _)| *id)
+                .zip(distances.iter().cloned()),
+        ) else {
+            break;
+        };
+
+        for ((id, _), distance) in candidates.iter().zip(distances.iter_mut()) {
+            *distance = best_id.eq(*id).if_then_else(&taken, distance);
+        }
+        shortlist.push(best_id);
+    }
+
+    Ok(shortlist)
+}
+
+/// Approximate squared distance from an encrypted point to the boundary of a public polygon.
+///
+/// Each edge, including the closing one from the last vertex back to the first, is sampled
+/// at its two endpoints and its midpoint, and the smallest squared distance to any sample is
+/// returned. This overestimates the true distance to an edge by up to half the edge length
+/// when the point is closest to the interior of a long edge, so split long edges into
+/// shorter ones for tighter results. The midpoints are taken in plaintext degrees, which is
+/// fine for fences that do not cross the antimeridian. The samples are encoded like
+/// `Point::scaled`, so `point` must use `DistanceMode::Degrees`. Panics if `vertices` is
+/// empty.
+pub fn distance_to_polygon_edge(point: &ClientData, vertices: &[Point]) -> FheUint32 {
+    let midpoints = vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| Point::new((a.lat + b.lat) / 2.0, (a.lon + b.lon) / 2.0));
+
+    vertices
+        .iter()
+        .copied()
+        .chain(midpoints)
+        .map(|sample| squared_distance_to_public(point, &sample))
+        .reduce(|nearest, distance| nearest.min(&distance))
+        .expect("polygon must have at least one vertex")
+}
+
+/// Predicate evaluated by `query` on an encrypted point and a reference.
+#[derive(Clone, Copy)]
+pub enum CompareOp<'a> {
+    /// The point is strictly closer to the reference than the given point is.
+    CloserThan(&'a ClientData),
+    /// The point is strictly farther from the reference than the given point is.
+    FartherThan(&'a ClientData),
+    /// The point lies within the given number of kilometers of the reference.
+    WithinKm(u32),
+    /// The point lies more than the given number of kilometers from the reference.
+    FartherThanKm(u32),
+}
+
+/// Evaluates `op` on `point` relative to `reference` and returns the encrypted answer.
+///
+/// Radii are converted with `KM_PER_DEGREE`, like `nearest_within`, and saturate at the
+/// largest squared distance a `FheUint32` can hold. They share its limits: ground kilometers
+/// north-south only with `Degrees` points, and points beyond the range of `squared_distance`
+/// can wrap inside any radius; `query_global` avoids the latter.
+pub fn query(point: &ClientData, reference: &ClientData, op: CompareOp) -> FheBool {
+    query_by(
+        point,
+        reference,
+        op,
+        squared_distance,
+        scaled_radius_squared,
+    )
+}
+
+/// Like `query`, but through `squared_distance_global`, so the answer stays correct for
+/// points anywhere on the globe. Radii are rounded down to the 710 m grid of that function.
+pub fn query_global(point: &ClientData, reference: &ClientData, op: CompareOp) -> FheBool {
+    query_by(
+        point,
+        reference,
+        op,
+        squared_distance_global,
+        global_radius_squared,
+    )
+}
+
+fn query_by(
+    point: &ClientData,
+    reference: &ClientData,
+    op: CompareOp,
+    distance: SquaredDistanceFn,
+    radius_squared: fn(f64) -> u32,
+) -> FheBool {
+    let own = distance(point, reference);
+    match op {
+        CompareOp::CloserThan(other) => own.lt(&distance(other, reference)),
+        CompareOp::FartherThan(other) => own.gt(&distance(other, reference)),
+        CompareOp::WithinKm(km) => own.le(radius_squared(km as f64)),
+        CompareOp::FartherThanKm(km) => own.gt(radius_squared(km as f64)),
+    }
+}
+
+/// Assigns every candidate to its nearest reference, as in a Voronoi partition.
+///
+/// Returns, per candidate, the encrypted index into `references` of the nearest one. Ties
+/// resolve to the lowest index. Costs one argmin over the references per candidate. Panics
+/// if `references` is empty.
+pub fn assign_to_references(
+    candidates: &[ClientData],
+    references: &[ClientData],
+) -> Vec<FheUint32> {
+    candidates
+        .iter()
+        .map(|candidate| find_nearest(references, candidate))
+        .collect()
+}
+
+/// Flags, per candidate, whether it is strictly closer to `reference` than every other one.
+///
+/// This is the boolean-mask counterpart to `find_nearest`: exactly one flag is true unless
+/// the nearest distance is shared, in which case all flags are false. Rather than comparing
+/// every pair, the minimum distance is found once and a candidate wins if it reaches that
+/// minimum and no other candidate does, so the cost stays linear in the number of
+/// candidates.
+pub fn is_strict_winner(candidates: &[ClientData], reference: &ClientData) -> Vec<FheBool> {
+    let distances: Vec<FheUint32> = candidates
+        .iter()
+        .map(|candidate| squared_distance(candidate, reference))
+        .collect();
+    let Some(nearest) = distances.iter().cloned().reduce(|a, b| a.min(&b)) else {
+        return Vec::new();
+    };
+
+    let at_minimum: Vec<FheBool> = distances
+        .iter()
+        .map(|distance| distance.eq(&nearest))
+        .collect();
+    let mut count = FheUint32::encrypt_trivial(0u32);
+    for flag in &at_minimum {
+        count += FheUint32::cast_from(flag.clone());
+    }
+    let unique = count.eq(1u32);
+
+    at_minimum.iter().map(|flag| flag & &unique).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tfhe::{generate_keys, set_server_key, ConfigBuilder};
+
+    /// Generates a fresh key pair and installs the server key on the calling thread.
+    pub(crate) fn keys() -> ClientKey {
+        let (client_key, server_keys) = generate_keys(ConfigBuilder::default().build());
+        set_server_key(server_keys);
+        client_key
+    }
+
+    pub(crate) fn encrypt(lat: f64, lon: f64, client_key: &ClientKey) -> ClientData {
+        ClientData::encrypt(&Point::new(lat, lon), client_key).unwrap()
+    }
+
+    fn zurich(client_key: &ClientKey) -> ClientData {
+        encrypt(47.3769, 8.5417, client_key)
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn global_comparison_orders_far_apart_points() {
+        let client_key = keys();
+        let london = encrypt(51.5074, -0.1278, &client_key);
+        let tokyo = encrypt(35.6762, 139.6503, &client_key);
+        let zurich = zurich(&client_key);
+
+        let london_closer: bool =
+            compare_distances_global(&london, &tokyo, &zurich).decrypt(&client_key);
+        let tokyo_closer: bool =
+            compare_distances_global(&tokyo, &london, &zurich).decrypt(&client_key);
+        assert!(london_closer);
+        assert!(!tokyo_closer);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn nearest_within_200_km() {
+        let client_key = keys();
+        let mode = DistanceMode::Planar {
+            reference_lat: 47.0,
+        };
+        let encrypt = |lat, lon| {
+            ClientData::encrypt_with_mode(&Point::new(lat, lon), mode, &client_key).unwrap()
+        };
+        let zurich = encrypt(47.3769, 8.5417);
+        // Munich (~246 km), Lugano (~156 km) and Vienna (~602 km) from Zurich
+        let candidates = [
+            encrypt(48.1351, 11.5820),
+            encrypt(46.0037, 8.9511),
+            encrypt(48.2082, 16.3738),
+        ];
+
+        let (found, index) = nearest_within(&candidates, &zurich, 200);
+        let found: bool = found.decrypt(&client_key);
+        let index: u32 = index.decrypt(&client_key);
+        assert!(found);
+        assert_eq!(index, 1);
+
+        let outside = [candidates[0].clone(), candidates[2].clone()];
+        let (found, index) = nearest_within(&outside, &zurich, 200);
+        let found: bool = found.decrypt(&client_key);
+        let index: u32 = index.decrypt(&client_key);
+        assert!(!found);
+        assert_eq!(index, 0);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn global_nearest_ignores_wrapped_far_candidate() {
+        let client_key = keys();
+        // 6.5536° due north of Zurich: 65536 units, whose square wraps to exactly 0
+        let far = encrypt(47.3769 + 6.5536, 8.5417, &client_key);
+        let basel = encrypt(47.5596, 7.5886, &client_key);
+        let zurich = zurich(&client_key);
+
+        let wrapped: u32 = squared_distance(&far, &zurich).decrypt(&client_key);
+        assert_eq!(wrapped, 0);
+
+        let candidates = [far, basel];
+        let nearest: u32 = find_nearest_global(&candidates, &zurich).decrypt(&client_key);
+        assert_eq!(nearest, 1);
+
+        let (found, index) = nearest_within_global(&candidates, &zurich, 50);
+        let found: bool = found.decrypt(&client_key);
+        assert!(!found);
+        let index: u32 = index.decrypt(&client_key);
+        assert_eq!(index, 0);
+    }
+
+    #[test]
+    fn offset_encoding_keeps_every_coordinate_non_negative_and_ordered() {
+        assert_eq!(Point::new(-90.0, -180.0).scaled(), (0, 0));
+        assert_eq!(
+            Point::new(90.0, 180.0).scaled(),
+            (1_800_000, FULL_TURN_SCALED)
+        );
+
+        let south = Point::new(-34.6037, -58.3816).scaled();
+        let further_south = Point::new(-34.9205, -57.9536).scaled();
+        assert!(further_south.0 < south.0);
+        assert!(further_south.1 > south.1);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn negative_latitude_ordering() {
+        let client_key = keys();
+        // La Plata (~53 km) and Montevideo (~205 km) against Buenos Aires
+        let la_plata = encrypt(-34.9205, -57.9536, &client_key);
+        let montevideo = encrypt(-34.9011, -56.1645, &client_key);
+        let buenos_aires = encrypt(-34.6037, -58.3816, &client_key);
+
+        let la_plata_closer: bool =
+            compare_distances(&la_plata, &montevideo, &buenos_aires).decrypt(&client_key);
+        assert!(la_plata_closer);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    #[should_panic(expected = "too large to interpolate")]
+    fn interpolate_planar_rejects_denominator_that_would_overflow() {
+        let client_key = keys();
+        let basel = encrypt(47.5596, 7.5886, &client_key);
+        let zurich = zurich(&client_key);
+        interpolate_planar(&basel, &zurich, 1, u32::MAX / FULL_TURN_SCALED + 1);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn weighted_centroid_matches_plaintext_mean() {
+        let client_key = keys();
+        let points = [
+            Point::new(47.5596, 7.5886),
+            Point::new(46.0037, 8.9511),
+            Point::new(47.3769, 8.5417),
+        ];
+        let weights = [5, 1, 2];
+        let encrypted: Vec<ClientData> = points
+            .iter()
+            .map(|point| ClientData::encrypt(point, &client_key).unwrap())
+            .collect();
+
+        let centroid = weighted_centroid(&encrypted, &weights);
+        let lat: u32 = centroid.lat.decrypt(&client_key);
+        let lon: u32 = centroid.lon.decrypt(&client_key);
+
+        let total: u32 = weights.iter().sum();
+        let mean = |coordinate: fn((u32, u32)) -> u32| {
+            points
+                .iter()
+                .zip(weights)
+                .map(|(point, weight)| coordinate(point.scaled()) * weight)
+                .sum::<u32>()
+                / total
+        };
+        assert_eq!(lat, mean(|(lat, _)| lat));
+        assert_eq!(lon, mean(|(_, lon)| lon));
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    #[should_panic(expected = "too large to average")]
+    fn weighted_centroid_rejects_weights_that_would_overflow() {
+        let client_key = keys();
+        let points = [zurich(&client_key), zurich(&client_key)];
+        weighted_centroid(&points, &[u32::MAX, 1]);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn relative_encoding_resolves_points_the_absolute_encoding_merges() {
+        let client_key = keys();
+        let reference = Point::new(47.3769, 8.5417);
+        // 0.00016° and 0.00024° north both round to 2 units at SCALE_FACTOR, but to 2 and 4
+        // units at RELATIVE_SCALE_FACTOR
+        let x = Point::new(reference.lat + 0.00016, reference.lon);
+        let y = Point::new(reference.lat + 0.00024, reference.lon);
+
+        let absolute = |point: &Point| ClientData::encrypt(point, &client_key).unwrap();
+        let absolute_x_closer: bool =
+            compare_distances(&absolute(&x), &absolute(&y), &absolute(&reference))
+                .decrypt(&client_key);
+        assert!(!absolute_x_closer);
+
+        let relative = |point: &Point| precompute_relative(point, &reference, &client_key).unwrap();
+        let relative_x_closer: bool =
+            compare_relative_distances(&relative(&x), &relative(&y)).decrypt(&client_key);
+        assert!(relative_x_closer);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn relative_offset_limit_applies_to_ground_distance() {
+        let client_key = keys();
+        let reference = Point::new(47.3769, 8.5417);
+
+        // 3.5° of longitude at 47° N is about 2.4° on the ground
+        let east = Point::new(reference.lat, reference.lon + 3.5);
+        assert!(precompute_relative(&east, &reference, &client_key).is_ok());
+
+        let north = Point::new(reference.lat + 3.5, reference.lon);
+        assert!(matches!(
+            precompute_relative(&north, &reference, &client_key),
+            Err(GpsFheError::RelativeOffsetTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn basel_zurich_decrypts_to_about_74_km() {
+        let client_key = keys();
+        let basel = Point::new(47.5596, 7.5886);
+        let zurich = Point::new(47.3769, 8.5417);
+        let mode = DistanceMode::Planar {
+            reference_lat: (basel.lat + zurich.lat) / 2.0,
+        };
+        let basel = ClientData::encrypt_with_mode(&basel, mode, &client_key).unwrap();
+        let zurich = ClientData::encrypt_with_mode(&zurich, mode, &client_key).unwrap();
+
+        // The great-circle distance is about 74.5 km
+        let km = encrypted_distance(&basel, &zurich).decrypt_km(&client_key);
+        assert!((74.0..=75.0).contains(&km), "decrypted {} km", km);
+    }
+
+    #[test]
+    fn nearby_points_snap_to_the_same_cell_centre() {
+        let a = snap_to_grid(&Point::new(47.3712, 8.5431), 0.01);
+        let b = snap_to_grid(&Point::new(47.3788, 8.5474), 0.01);
+        assert_eq!(a, b);
+        assert!((a.lat - 47.375).abs() < 1e-9 && (a.lon - 8.545).abs() < 1e-9);
+
+        let south_west = snap_to_grid(&Point::new(-33.8688, -151.2093), 0.5);
+        assert_eq!(south_west, Point::new(-33.75, -151.25));
+    }
+
+    #[test]
+    #[should_panic(expected = "positive and finite")]
+    fn snap_to_grid_rejects_zero_cells() {
+        snap_to_grid(&Point::new(47.3769, 8.5417), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "positive and finite")]
+    fn snap_to_grid_rejects_negative_cells() {
+        snap_to_grid(&Point::new(47.3769, 8.5417), -0.01);
+    }
+
+    #[test]
+    #[should_panic(expected = "positive and finite")]
+    fn snap_to_grid_rejects_nan_cells() {
+        snap_to_grid(&Point::new(47.3769, 8.5417), f64::NAN);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn snapped_neighbours_compare_equal() {
+        let client_key = keys();
+        let snap = |lat, lon| {
+            let snapped = snap_to_grid(&Point::new(lat, lon), 0.01);
+            ClientData::encrypt(&snapped, &client_key).unwrap()
+        };
+        let a = snap(47.3712, 8.5431);
+        let b = snap(47.3788, 8.5474);
+        let basel = encrypt(47.5596, 7.5886, &client_key);
+
+        let distance: u32 = squared_distance(&a, &b).decrypt(&client_key);
+        assert_eq!(distance, 0);
+        let a_closer: bool = compare_distances(&a, &b, &basel).decrypt(&client_key);
+        let b_closer: bool = compare_distances(&b, &a, &basel).decrypt(&client_key);
+        assert!(!a_closer && !b_closer);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn batch_encryption_matches_sequential_encryption() {
+        let client_key = keys();
+        let mode = DistanceMode::Planar {
+            reference_lat: 47.0,
+        };
+        let points: Vec<Point> = (0..16)
+            .map(|i| Point::new(-80.0 + 10.0 * i as f64, 170.0 - 21.5 * i as f64))
+            .collect();
+
+        let batch = ClientData::encrypt_batch(&points, mode, &client_key).unwrap();
+        assert_eq!(batch.len(), points.len());
+        for (point, data) in points.iter().zip(&batch) {
+            let sequential = ClientData::encrypt_with_mode(point, mode, &client_key).unwrap();
+            let batch_lat: u32 = data.lat.decrypt(&client_key);
+            let batch_lon: u32 = data.lon.decrypt(&client_key);
+            let lat: u32 = sequential.lat.decrypt(&client_key);
+            let lon: u32 = sequential.lon.decrypt(&client_key);
+            assert_eq!((batch_lat, batch_lon), (lat, lon));
+            assert_eq!((lat, lon), point.scaled_with_mode(mode));
+        }
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn cross_distances_follow_the_great_circle_baseline() {
+        let client_key = keys();
+        let mode = DistanceMode::Planar {
+            reference_lat: 47.0,
+        };
+        let encrypt =
+            |point: &Point| ClientData::encrypt_with_mode(point, mode, &client_key).unwrap();
+
+        // A near-tie: courier A is 10.0 km south of pickup P, courier B 9.9 km west of pickup Q
+        let a = Point::new(47.0, 8.0);
+        let p = Point::new(47.09, 8.0);
+        let b = Point::new(47.0, 9.0);
+        let q = Point::new(47.0, 9.131);
+        // A pickup 5.6 km from A, which makes the ordering clear either way round
+        let near_p = Point::new(47.05, 8.0);
+
+        let cases = [(a, near_p, b, q), (b, q, a, near_p), (a, p, b, q)];
+        for (a, p, b, q) in cases {
+            let a_nearer: bool =
+                compare_cross_distances(&encrypt(&a), &encrypt(&p), &encrypt(&b), &encrypt(&q))
+                    .decrypt(&client_key);
+            assert_eq!(a_nearer, haversine_km(&a, &p) < haversine_km(&b, &q));
+        }
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn interpolated_midpoint_is_near_the_great_circle_midpoint() {
+        let client_key = keys();
+        let basel = Point::new(47.5596, 7.5886);
+        let zurich = Point::new(47.3769, 8.5417);
+
+        let midpoint = interpolate_planar(
+            &ClientData::encrypt(&basel, &client_key).unwrap(),
+            &ClientData::encrypt(&zurich, &client_key).unwrap(),
+            1,
+            2,
+        );
+        let lat: u32 = midpoint.lat.decrypt(&client_key);
+        let lon: u32 = midpoint.lon.decrypt(&client_key);
+        let decrypted = Point::new(
+            lat as f64 / SCALE_FACTOR - LAT_OFFSET_DEG,
+            lon as f64 / SCALE_FACTOR - LON_OFFSET_DEG,
+        );
+
+        // Great-circle midpoint of the two cities
+        let (lat1, lon1) = (basel.lat.to_radians(), basel.lon.to_radians());
+        let (lat2, d_lon) = (
+            zurich.lat.to_radians(),
+            (zurich.lon - basel.lon).to_radians(),
+        );
+        let (bx, by) = (lat2.cos() * d_lon.cos(), lat2.cos() * d_lon.sin());
+        let great_circle = Point::new(
+            (lat1.sin() + lat2.sin())
+                .atan2(((lat1.cos() + bx).powi(2) + by.powi(2)).sqrt())
+                .to_degrees(),
+            (lon1 + by.atan2(lat1.cos() + bx)).to_degrees(),
+        );
+
+        // The straight line and the great circle part by about 130 m over these 74 km
+        let offset_km = haversine_km(&decrypted, &great_circle);
+        assert!(offset_km < 0.2, "midpoint is {} km off", offset_km);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn large_reference_uncertainty_makes_a_clear_ordering_ambiguous() {
+        let client_key = keys();
+        let [basel, lugano, zurich] =
+            default_demo_points().map(|(_, point)| encrypt(point.lat, point.lon, &client_key));
+
+        // Basel and Lugano are about 9704 and 14329 units from Zurich, 4625 units apart
+        let exact: u8 = compare_distances_with_reference_uncertainty(&basel, &lugano, &zurich, 0)
+            .decrypt(&client_key);
+        assert_eq!(exact, REFERENCE_CLOSER);
+
+        // 50 km of error allows a difference of 2 * 4491 units
+        let uncertain: u8 =
+            compare_distances_with_reference_uncertainty(&basel, &lugano, &zurich, 50)
+                .decrypt(&client_key);
+        assert_eq!(uncertain, REFERENCE_AMBIGUOUS);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn encrypted_extent_matches_plaintext_bounding_box() {
+        let client_key = keys();
+        let cities = [
+            Point::new(47.5596, 7.5886),
+            Point::new(46.0037, 8.9511),
+            Point::new(47.3769, 8.5417),
+            Point::new(46.9480, 7.4474),
+            Point::new(46.2044, 6.1432),
+        ];
+        let encrypted: Vec<ClientData> = cities
+            .iter()
+            .map(|city| ClientData::encrypt(city, &client_key).unwrap())
+            .collect();
+
+        let (min_lat, max_lat, min_lon, max_lon) = encrypted_extent(&encrypted);
+        let decrypted: [u32; 4] =
+            [min_lat, max_lat, min_lon, max_lon].map(|coordinate| coordinate.decrypt(&client_key));
+
+        let scaled: Vec<(u32, u32)> = cities.iter().map(Point::scaled).collect();
+        let lats = scaled.iter().map(|&(lat, _)| lat);
+        let lons = scaled.iter().map(|&(_, lon)| lon);
+        let expected = [
+            lats.clone().min().unwrap(),
+            lats.max().unwrap(),
+            lons.clone().min().unwrap(),
+            lons.max().unwrap(),
+        ];
+        assert_eq!(decrypted, expected);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn public_candidates_compare_like_encrypted_ones() {
+        let client_key = keys();
+        let [(_, basel), (_, lugano), (_, zurich)] = default_demo_points();
+        let encrypted = |point: &Point| ClientData::encrypt(point, &client_key).unwrap();
+        let reference = encrypted(&zurich);
+
+        for (x, y) in [(basel, lugano), (lugano, basel)] {
+            let public: bool = compare_public_candidates(&x, &y, &reference).decrypt(&client_key);
+            let private: bool =
+                compare_distances(&encrypted(&x), &encrypted(&y), &reference).decrypt(&client_key);
+            assert_eq!(public, private);
+            assert_eq!(public, x == basel);
+        }
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn ring_counts_match_plaintext_rings() {
+        let client_key = keys();
+        let reference = Point::new(47.3769, 8.5417);
+        let edges = [10, 20, 50];
+
+        // Points due north and south of the reference, so the encoded distance runs along a
+        // meridian and matches the ground; the last two fall outside every ring
+        let points: Vec<Point> = [
+            3.0, -8.0, 12.0, -15.0, 19.0, -25.0, 40.0, -49.0, 70.0, -120.0,
+        ]
+        .iter()
+        .map(|km| Point::new(reference.lat + km / KM_PER_DEGREE, reference.lon))
+        .collect();
+        let encrypted: Vec<ClientData> = points
+            .iter()
+            .map(|point| ClientData::encrypt(point, &client_key).unwrap())
+            .collect();
+
+        let counts: Vec<u32> = ring_counts(
+            &encrypted,
+            &encrypt(reference.lat, reference.lon, &client_key),
+            &edges,
+        )
+        .iter()
+        .map(|count| count.decrypt(&client_key))
+        .collect();
+
+        let mut expected = vec![0; edges.len()];
+        for point in &points {
+            let km = haversine_km(point, &reference);
+            if let Some(ring) = edges.iter().position(|&edge| km <= edge as f64) {
+                expected[ring] += 1;
+            }
+        }
+        assert_eq!(expected, [2, 3, 3]);
+        assert_eq!(counts, expected);
+    }
+
+    #[test]
+    fn squared_distance_fits_only_within_about_6_5_degrees() {
+        let zurich = Point::new(47.3769, 8.5417);
+        let basel = Point::new(47.5596, 7.5886);
+        let new_york = Point::new(40.7128, -74.006);
+        let mode = DistanceMode::Degrees;
+
+        assert!(squared_distance_fits(&basel, &zurich, mode));
+        assert!(!squared_distance_fits(&new_york, &zurich, mode));
+        // 65535 units due north still fit; 65536 square to exactly 2^32
+        let north = |units: f64| Point::new(zurich.lat + units / SCALE_FACTOR, zurich.lon);
+        assert!(squared_distance_fits(&north(65535.0), &zurich, mode));
+        assert!(!squared_distance_fits(&north(65536.0), &zurich, mode));
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn deduplicate_keeps_the_first_of_each_near_duplicate() {
+        let client_key = keys();
+        // The second point is 3 units (about 33 m) north of the first, within 100 m
+        let points = [
+            zurich(&client_key),
+            encrypt(47.3772, 8.5417, &client_key),
+            encrypt(47.5596, 7.5886, &client_key),
+        ];
+
+        let keep: Vec<bool> = deduplicate(&points, 100)
+            .iter()
+            .map(|flag| flag.decrypt(&client_key))
+            .collect();
+        assert_eq!(keep, [true, false, true]);
+    }
+
+    /// Winterthur, Lugano and Lucerne, about 19, 156 and 40 km from Zurich.
+    fn swiss_candidates(client_key: &ClientKey) -> [ClientData; 3] {
+        [
+            encrypt(47.4988, 8.7237, client_key),
+            encrypt(46.0037, 8.9511, client_key),
+            encrypt(47.0502, 8.3093, client_key),
+        ]
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn nearest_among_selected_ids() {
+        let client_key = keys();
+        let candidates = swiss_candidates(&client_key);
+        let zurich = zurich(&client_key);
+
+        // Winterthur is nearest overall but not selected
+        let index: u32 = find_nearest_among(&candidates, &zurich, &[1, 2])
+            .unwrap()
+            .decrypt(&client_key);
+        assert_eq!(index, 2);
+
+        assert!(matches!(
+            find_nearest_among(&candidates, &zurich, &[0, 3, 5]),
+            Err(GpsFheError::MissingCandidates(ids)) if ids == [3, 5]
+        ));
+        assert!(matches!(
+            find_nearest_among(&candidates, &zurich, &[]),
+            Err(GpsFheError::NoCandidatesSelected)
+        ));
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn masked_out_nearest_candidate_loses() {
+        let client_key = keys();
+        let candidates = swiss_candidates(&client_key);
+        let mask = [false, true, true].map(|selected| FheBool::encrypt(selected, &client_key));
+
+        let index: u32 =
+            find_nearest_masked(&candidates, &zurich(&client_key), &mask).decrypt(&client_key);
+        assert_eq!(index, 2);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn nearest_with_distance_matches_the_great_circle_baseline() {
+        let client_key = keys();
+        let reference = Point::new(47.3769, 8.5417);
+        let mode = DistanceMode::Planar {
+            reference_lat: reference.lat,
+        };
+        // Basel, Lugano and Winterthur
+        let candidates = [
+            Point::new(47.5596, 7.5886),
+            Point::new(46.0037, 8.9511),
+            Point::new(47.4988, 8.7237),
+        ];
+        let encrypted = ClientData::encrypt_batch(&candidates, mode, &client_key).unwrap();
+        let encrypted_reference =
+            ClientData::encrypt_with_mode(&reference, mode, &client_key).unwrap();
+
+        let (index, distance) = find_nearest_with_distance(&encrypted, &encrypted_reference);
+        let index: u32 = index.decrypt(&client_key);
+        let km = decrypt_distance_km(&distance, &client_key);
+
+        let baseline: Vec<f64> = candidates
+            .iter()
+            .map(|candidate| haversine_km(candidate, &reference))
+            .collect();
+        assert_eq!(index, 2);
+        assert!(baseline.iter().all(|&other| baseline[2] <= other));
+        // Winterthur is 19.3 km away on the great circle
+        assert!((km - baseline[2]).abs() < 0.1, "decrypted {} km", km);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn masking_preserves_the_ordering() {
+        let client_key = keys();
+        let [basel, lugano, zurich] =
+            default_demo_points().map(|(_, point)| encrypt(point.lat, point.lon, &client_key));
+
+        // Arbitrary masks, including ones that would wrap a 32-bit sum
+        for mask in [0x1234_5678u32, 0x7fff_fff0, u32::MAX - 1_000] {
+            let mask = FheUint32::encrypt(mask, &client_key);
+            let basel_closer: bool =
+                compare_distances_masked(&basel, &lugano, &zurich, &mask).decrypt(&client_key);
+            let lugano_closer: bool =
+                compare_distances_masked(&lugano, &basel, &zurich, &mask).decrypt(&client_key);
+            assert!(basel_closer && !lugano_closer);
+        }
+    }
+
+    #[test]
+    fn one_ulp_perturbations_move_the_encoding_by_at_most_one_unit() {
+        // Demo coordinates, a value that scales to just below an integer (7.5886° E) and
+        // values exactly halfway between two units
+        let seeds = [
+            Point::new(47.5596, 7.5886),
+            Point::new(46.0037, 8.9511),
+            Point::new(-34.60375, -58.38165),
+            Point::new(0.00005, 179.99995),
+        ];
+
+        for seed in seeds {
+            let mut point = seed;
+            for _ in 0..64 {
+                point = Point::new(point.lat.next_down(), point.lon.next_down());
+            }
+
+            let mut previous = point.scaled();
+            for _ in 0..128 {
+                point = Point::new(point.lat.next_up(), point.lon.next_up());
+                let current = point.scaled();
+                assert!(
+                    (previous.0..=previous.0 + 1).contains(&current.0)
+                        && (previous.1..=previous.1 + 1).contains(&current.1),
+                    "{:?} encodes to {:?} after {:?}",
+                    point,
+                    current,
+                    previous
+                );
+                previous = current;
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn named_distances_keep_names_and_great_circle_order() {
+        let client_key = keys();
+        let zurich = Point::new(47.3769, 8.5417);
+        let landmarks = [
+            ("Basel", Point::new(47.5596, 7.5886)),
+            ("Lugano", Point::new(46.0037, 8.9511)),
+            ("Winterthur", Point::new(47.4988, 8.7237)),
+        ];
+        let encrypted: Vec<ClientData> = landmarks
+            .iter()
+            .map(|(name, point)| {
+                ClientData::encrypt(point, &client_key)
+                    .unwrap()
+                    .with_name(*name)
+            })
+            .collect();
+
+        let distances = distances_to_named(
+            &ClientData::encrypt(&zurich, &client_key).unwrap(),
+            &encrypted,
+        );
+        let mut decrypted: Vec<(String, u32)> = distances
+            .into_iter()
+            .map(|(name, distance)| (name.unwrap(), distance.decrypt(&client_key)))
+            .collect();
+        let names: Vec<&str> = decrypted.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["Basel", "Lugano", "Winterthur"]);
+
+        let mut baseline = landmarks.to_vec();
+        decrypted.sort_by_key(|&(_, distance)| distance);
+        baseline.sort_by(|(_, a), (_, b)| {
+            haversine_km(a, &zurich).total_cmp(&haversine_km(b, &zurich))
+        });
+        let by_distance: Vec<&str> = decrypted.iter().map(|(name, _)| name.as_str()).collect();
+        let by_baseline: Vec<&str> = baseline.iter().map(|&(name, _)| name).collect();
+        assert_eq!(by_distance, by_baseline);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn planar_mode_orders_small_latitude_and_longitude_differences() {
+        let client_key = keys();
+        let zurich = Point::new(47.3769, 8.5417);
+        let mode = DistanceMode::Planar {
+            reference_lat: zurich.lat,
+        };
+        let encrypt =
+            |point: &Point| ClientData::encrypt_with_mode(point, mode, &client_key).unwrap();
+
+        // 111 m and 222 m north, and 90 m east, of Zurich. In raw degrees the eastern point
+        // is 12 units away against 10 for the nearer northern one, the wrong way round.
+        let north = Point::new(zurich.lat + 0.001, zurich.lon);
+        let further_north = Point::new(zurich.lat + 0.002, zurich.lon);
+        let east = Point::new(zurich.lat, zurich.lon + 0.0012);
+        let reference = encrypt(&zurich);
+
+        for (x, y) in [(north, further_north), (north, east)] {
+            let x_closer: bool =
+                compare_distances(&encrypt(&x), &encrypt(&y), &reference).decrypt(&client_key);
+            assert_eq!(
+                x_closer,
+                haversine_km(&x, &zurich) < haversine_km(&y, &zurich)
+            );
+        }
+    }
+
+    #[test]
+    fn default_demo_points_are_basel_lugano_and_zurich() {
+        assert_eq!(
+            default_demo_points(),
+            [
+                ("Basel", Point::new(47.5596, 7.5886)),
+                ("Lugano", Point::new(46.0037, 8.9511)),
+                ("Zurich", Point::new(47.3769, 8.5417)),
+            ]
+        );
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn two_tight_clusters_get_two_ids() {
+        let client_key = keys();
+        // Two points about 1.4 km apart in Zurich and two in Basel, 74 km away
+        let points = [
+            zurich(&client_key),
+            encrypt(47.3869, 8.5517, &client_key),
+            encrypt(47.5596, 7.5886, &client_key),
+            encrypt(47.5696, 7.5786, &client_key),
+        ];
+
+        let ids: Vec<u32> = cluster(&points, 5)
+            .iter()
+            .map(|id| id.decrypt(&client_key))
+            .collect();
+        assert_eq!(ids, [0, 0, 2, 2]);
+    }
+
+    #[test]
+    fn default_scale_resolves_about_11_m() {
+        let (ns_m, ew_m) = quantization_resolution_m(0.0);
+        assert!((ns_m - 11.132).abs() < 1e-9 && (ew_m - 11.132).abs() < 1e-9);
+
+        // A degree of longitude at 60° spans half the ground it does at the equator
+        let (ns_m, ew_m) = quantization_resolution_m(60.0);
+        assert!((ns_m - 11.132).abs() < 1e-9 && (ew_m - 5.566).abs() < 1e-9);
+        assert_eq!(quantization_resolution_m(-60.0), (ns_m, ew_m));
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn points_between_basel_and_zurich_are_nearer_the_closer_city() {
+        let client_key = keys();
+        let basel = Point::new(47.5596, 7.5886);
+        let zurich = Point::new(47.3769, 8.5417);
+        let encrypted_basel = ClientData::encrypt(&basel, &client_key).unwrap();
+        let encrypted_zurich = ClientData::encrypt(&zurich, &client_key).unwrap();
+
+        // About 16 km from Basel, then about 21 km from Zurich
+        for point in [Point::new(47.52, 7.8), Point::new(47.47, 8.3)] {
+            let encrypted = ClientData::encrypt(&point, &client_key).unwrap();
+            let nearer_basel: bool =
+                nearer_to(&encrypted, &encrypted_basel, &encrypted_zurich).decrypt(&client_key);
+            assert_eq!(
+                nearer_basel,
+                haversine_km(&point, &basel) < haversine_km(&point, &zurich)
+            );
+        }
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn nearest_public_ids_returns_the_top_two_in_order() {
+        let client_key = keys();
+        let cities = [
+            (101, encrypt(47.5596, 7.5886, &client_key)),
+            (102, encrypt(46.0037, 8.9511, &client_key)),
+            (103, encrypt(47.4988, 8.7237, &client_key)),
+            (104, encrypt(46.9480, 7.4474, &client_key)),
+            (105, encrypt(47.0502, 8.3093, &client_key)),
+        ];
+
+        // Winterthur, then Lucerne; Basel, Bern and Lugano are all farther from Zurich
+        let ids: Vec<u32> = nearest_public_ids(&cities, &zurich(&client_key), 2)
+            .unwrap()
+            .iter()
+            .map(|id| id.decrypt(&client_key))
+            .collect();
+        assert_eq!(ids, [103, 105]);
+
+        // Asking for more than there are returns all of them
+        let all = nearest_public_ids(&cities[..2], &zurich(&client_key), 3).unwrap();
+        assert_eq!(all.len(), 2);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn nearest_public_ids_rejects_duplicate_ids() {
+        let client_key = keys();
+        let cities = [
+            (7, encrypt(47.5596, 7.5886, &client_key)),
+            (3, encrypt(46.0037, 8.9511, &client_key)),
+            (7, encrypt(47.4988, 8.7237, &client_key)),
+            (3, encrypt(46.9480, 7.4474, &client_key)),
+            (7, encrypt(47.0502, 8.3093, &client_key)),
+        ];
+
+        assert!(matches!(
+            nearest_public_ids(&cities, &zurich(&client_key), 2),
+            Err(GpsFheError::DuplicateCandidateIds(ids)) if ids == [3, 7]
+        ));
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn distance_to_triangle_edge_is_the_nearest_sample() {
+        let client_key = keys();
+        let triangle = [
+            Point::new(47.30, 8.45),
+            Point::new(47.45, 8.50),
+            Point::new(47.35, 8.65),
+        ];
+        let zurich_point = Point::new(47.3769, 8.5417);
+
+        // The vertices, then the midpoints of each edge including the closing one
+        let samples = triangle.iter().copied().chain(
+            triangle
+                .iter()
+                .zip(triangle.iter().cycle().skip(1))
+                .map(|(a, b)| Point::new((a.lat + b.lat) / 2.0, (a.lon + b.lon) / 2.0)),
+        );
+        let (lat, lon) = zurich_point.scaled();
+        let expected = samples
+            .map(|sample| {
+                let (s_lat, s_lon) = sample.scaled();
+                lat.abs_diff(s_lat).pow(2) + lon.abs_diff(s_lon).pow(2)
+            })
+            .min()
+            .unwrap();
+
+        let distance: u32 =
+            distance_to_polygon_edge(&zurich(&client_key), &triangle).decrypt(&client_key);
+        assert_eq!(distance, expected);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn each_compare_op_on_the_swiss_cities() {
+        let client_key = keys();
+        let basel = encrypt(47.5596, 7.5886, &client_key);
+        let lugano = encrypt(46.0037, 8.9511, &client_key);
+        let zurich = zurich(&client_key);
+
+        // Basel is about 108 km from Zurich under the Degrees encoding, Lugano about 160 km
+        let cases = [
+            (CompareOp::CloserThan(&lugano), true),
+            (CompareOp::FartherThan(&lugano), false),
+            (CompareOp::WithinKm(120), true),
+            (CompareOp::WithinKm(100), false),
+            (CompareOp::FartherThanKm(100), true),
+            (CompareOp::FartherThanKm(120), false),
+        ];
+        for (i, (op, expected)) in cases.into_iter().enumerate() {
+            let answer: bool = query(&basel, &zurich, op).decrypt(&client_key);
+            assert_eq!(answer, expected, "case {}", i);
+        }
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn candidates_are_assigned_to_the_nearer_of_zurich_and_bern() {
+        let client_key = keys();
+        let references = [zurich(&client_key), encrypt(46.9480, 7.4474, &client_key)];
+        // Basel, Winterthur, near Fribourg, and near Lucerne
+        let candidates = [
+            encrypt(47.5596, 7.5886, &client_key),
+            encrypt(47.4988, 8.7237, &client_key),
+            encrypt(46.9, 7.2, &client_key),
+            encrypt(47.05, 8.31, &client_key),
+        ];
+
+        let assigned: Vec<u32> = assign_to_references(&candidates, &references)
+            .iter()
+            .map(|index| index.decrypt(&client_key))
+            .collect();
+        assert_eq!(assigned, [1, 0, 1, 0]);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn only_the_nearest_of_five_cities_is_a_strict_winner() {
+        let client_key = keys();
+        let zurich = zurich(&client_key);
+        let cities = [
+            (47.5596, 7.5886),
+            (46.0037, 8.9511),
+            (47.4988, 8.7237),
+            (46.9480, 7.4474),
+            (47.0502, 8.3093),
+        ];
+        let decrypt = |flags: Vec<FheBool>| -> Vec<bool> {
+            flags.iter().map(|flag| flag.decrypt(&client_key)).collect()
+        };
+
+        // Only Winterthur is nearest to Zurich
+        let candidates: Vec<ClientData> = cities
+            .iter()
+            .map(|&(lat, lon)| encrypt(lat, lon, &client_key))
+            .collect();
+        assert_eq!(
+            decrypt(is_strict_winner(&candidates, &zurich)),
+            [false, false, true, false, false]
+        );
+
+        // A second Winterthur ties the nearest distance, so nobody wins
+        let mut tied = candidates;
+        tied.push(encrypt(47.4988, 8.7237, &client_key));
+        assert_eq!(decrypt(is_strict_winner(&tied, &zurich)), [false; 6]);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn trivial_reference_matches_a_client_encrypted_one() {
+        let client_key = keys();
+        let basel = encrypt(47.5596, 7.5886, &client_key);
+        let lugano = encrypt(46.0037, 8.9511, &client_key);
+        let trivial_zurich = trivial_point(47.3769, 8.5417);
+
+        let distance: u32 = squared_distance(&basel, &trivial_zurich).decrypt(&client_key);
+        let expected: u32 = squared_distance(&basel, &zurich(&client_key)).decrypt(&client_key);
+        assert_eq!(distance, expected);
+
+        let x_closer: bool =
+            compare_distances(&basel, &lugano, &trivial_zurich).decrypt(&client_key);
+        assert!(x_closer);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn reduced_precision_keeps_the_full_precision_ordering() {
+        let client_key = keys();
+        let zurich = zurich(&client_key);
+        // Basel, Lugano, Winterthur and Lucerne
+        let cities = [
+            Point::new(47.5596, 7.5886),
+            Point::new(46.0037, 8.9511),
+            Point::new(47.4988, 8.7237),
+            Point::new(47.0502, 8.3093),
+        ];
+
+        // 16 bits is a grid of about 710 m, far finer than the gaps between the cities
+        for precision_bits in [COORDINATE_BITS, 16] {
+            let candidates: Vec<ClientData> = cities
+                .iter()
+                .map(|city| {
+                    ClientData::encrypt_with_precision(city, precision_bits, &client_key).unwrap()
+                })
+                .collect();
+
+            let nearest: u32 = find_nearest(&candidates, &zurich).decrypt(&client_key);
+            assert_eq!(nearest, 2, "{} bits", precision_bits);
+            let lucerne_closer: bool =
+                compare_distances(&candidates[3], &candidates[0], &zurich).decrypt(&client_key);
+            assert!(lucerne_closer, "{} bits", precision_bits);
+        }
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn ground_meters_follows_the_great_circle_where_degrees_does_not() {
+        let client_key = keys();
+        let reference = Point::new(50.0, 0.0);
+        // About 444 km to the north-east and 500 km due south
+        let east = Point::new(52.0, 5.5);
+        let south = Point::new(45.5, 0.0);
+        let east_closer = haversine_km(&east, &reference) < haversine_km(&south, &reference);
+        assert!(east_closer);
+
+        // Degrees counts a degree of longitude at 52° as a full degree of latitude
+        for (mode, expected) in [
+            (DistanceMode::Degrees, false),
+            (DistanceMode::GroundMeters { central_lon: 0.0 }, true),
+        ] {
+            let [x, y, z] = [east, south, reference]
+                .map(|point| ClientData::encrypt_with_mode(&point, mode, &client_key).unwrap());
+            let x_closer: bool = compare_distances(&x, &y, &z).decrypt(&client_key);
+            assert_eq!(x_closer, expected, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn wrapped_distance_crosses_the_antimeridian_near_fiji() {
+        let client_key = keys();
+        let reference = Point::new(-17.5, 180.0);
+        // About 77 km east across the antimeridian, and 169 km west on the same side
+        let east = Point::new(-17.0, -179.5);
+        let west = Point::new(-18.5, 178.8);
+        assert!(haversine_km(&east, &reference) < haversine_km(&west, &reference));
+
+        let [x, y, z] = [east, west, reference].map(|p| encrypt(p.lat, p.lon, &client_key));
+        let x_closer: bool = squared_distance_wrapped(&x, &z)
+            .lt(&squared_distance_wrapped(&y, &z))
+            .decrypt(&client_key);
+        assert!(x_closer);
+
+        // The plain distance sees the east point almost a full turn away, which wraps
+        let x_closer: bool = compare_distances(&x, &y, &z).decrypt(&client_key);
+        assert!(!x_closer);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn global_distance_orders_tokyo_and_honolulu_across_the_pacific() {
+        let client_key = keys();
+        let reference = Point::new(30.0, 180.0);
+        let tokyo = Point::new(35.6762, 139.6503);
+        let honolulu = Point::new(21.3069, -157.8583);
+        // About 3800 km to Tokyo and 2400 km to Honolulu
+        assert!(haversine_km(&honolulu, &reference) < haversine_km(&tokyo, &reference));
+
+        let [x, y, z] = [honolulu, tokyo, reference].map(|p| encrypt(p.lat, p.lon, &client_key));
+        let x_closer: bool = compare_distances_global(&x, &y, &z).decrypt(&client_key);
+        assert!(x_closer);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn abs_diff_of_known_values() {
+        let client_key = keys();
+        let cases: [(u32, u32, u32); 4] = [
+            (1_375_596, 1_373_769, 1_827),
+            (1_373_769, 1_375_596, 1_827),
+            (1_373_769, 1_373_769, 0),
+            (0, u32::MAX, u32::MAX),
+        ];
+
+        for (a, b, expected) in cases {
+            let diff: u32 = fhe_abs_diff(
+                &FheUint32::encrypt(a, &client_key),
+                &FheUint32::encrypt(b, &client_key),
+            )
+            .decrypt(&client_key);
+            assert_eq!(diff, expected, "|{} - {}|", a, b);
+        }
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn isqrt_of_known_values() {
+        let client_key = keys();
+        let cases: [(u32, u32); 7] = [
+            (0, 0),
+            (1, 1),
+            (15, 3),
+            (16, 4),
+            (65_535 * 65_535 - 1, 65_534),
+            (65_535 * 65_535, 65_535),
+            (u32::MAX, 65_535),
+        ];
+
+        for (value, expected) in cases {
+            let root: u32 = fhe_isqrt(&FheUint32::encrypt(value, &client_key)).decrypt(&client_key);
+            assert_eq!(root, expected, "isqrt({})", value);
+        }
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn linear_distance_is_the_floor_square_root_of_the_squared_one() {
+        let client_key = keys();
+        let basel = encrypt(47.5596, 7.5886, &client_key);
+
+        let (squared, linear) = distance_both(&basel, &zurich(&client_key));
+        let squared: u32 = squared.decrypt(&client_key);
+        let linear: u32 = linear.decrypt(&client_key);
+        let linear = linear as u64;
+        assert!(
+            linear * linear <= squared as u64 && (squared as u64) < (linear + 1) * (linear + 1),
+            "{} is not the square root of {}",
+            linear,
+            squared
+        );
+    }
+}
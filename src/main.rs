@@ -1,64 +1,305 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde_json::json;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tfhe::prelude::*;
-use tfhe::{generate_keys, set_server_key, ConfigBuilder, FheUint32};
-use std::time::Instant;
+use tfhe::{set_server_key, ClientKey, CompressedServerKey, ConfigBuilder};
+use tfhe_gps_distance::{
+    compare_distances, default_demo_points, encrypted_distance, find_nearest, read_frame,
+    squared_distance_fits, write_frame, ClientData, Crs, DistanceMode, DivergenceLog, Point,
+    KM_PER_DEGREE,
+};
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Homomorphic distance computations over encrypted GPS coordinates"
+)]
+struct Cli {
+    #[command(flatten)]
+    options: Options,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Flags shared by every subcommand.
+#[derive(Args)]
+struct Options {
+    /// Print results as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// How coordinates are encoded before encryption [default: planar for `distance`,
+    /// degrees otherwise]
+    #[arg(long, global = true, value_enum)]
+    mode: Option<Mode>,
+
+    /// Client key to reuse across runs; generated and written there if the file does not exist,
+    /// with the server key in FILE.server
+    #[arg(long, global = true, value_name = "FILE")]
+    keys: Option<PathBuf>,
+}
+
+/// Command-line names for the `DistanceMode` variants.
+#[derive(Clone, Copy, ValueEnum)]
+enum Mode {
+    /// Raw degrees on both axes
+    Degrees,
+    /// Longitude scaled by the cosine of the points' mean latitude
+    Planar,
+    /// Longitude scaled by the cosine of each point's own latitude
+    GroundMeters,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Determine which of X and Y is closer to Z (Basel, Lugano and Zurich by default)
+    Compare {
+        /// Points X, Y and Z as LAT,LON
+        #[arg(
+            num_args = 3,
+            value_names = ["X", "Y", "Z"],
+            value_parser = parse_point,
+            allow_hyphen_values = true
+        )]
+        points: Option<Vec<Point>>,
+        /// Append the points to this JSONL file if the result disagrees with the plaintext baseline
+        #[arg(long, value_name = "FILE")]
+        divergence_log: Option<PathBuf>,
+    },
+    /// Find the candidate closest to a reference point
+    Nearest {
+        /// Reference point as LAT,LON
+        #[arg(value_parser = parse_point, allow_hyphen_values = true)]
+        reference: Point,
+        /// Candidate points as LAT,LON
+        #[arg(required = true, value_parser = parse_point, allow_hyphen_values = true)]
+        candidates: Vec<Point>,
+    },
+    /// Compute the approximate distance between two points
+    Distance {
+        /// First point as LAT,LON
+        #[arg(value_parser = parse_point, allow_hyphen_values = true)]
+        a: Point,
+        /// Second point as LAT,LON
+        #[arg(value_parser = parse_point, allow_hyphen_values = true)]
+        b: Point,
+    },
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("Starting... Determining which point is closer to point Z...");
+    let cli = Cli::parse();
+
+    match cli.command {
+        None => compare(None, None, &cli.options),
+        Some(Command::Compare {
+            points,
+            divergence_log,
+        }) => compare(points, divergence_log, &cli.options),
+        Some(Command::Nearest {
+            reference,
+            candidates,
+        }) => nearest(&reference, &candidates, &cli.options),
+        Some(Command::Distance { a, b }) => distance(&a, &b, &cli.options),
+    }
+}
+
+/// Parses a `LAT,LON` pair in decimal degrees.
+fn parse_point(value: &str) -> Result<Point, String> {
+    let (lat, lon) = value
+        .split_once(',')
+        .ok_or_else(|| format!("expected LAT,LON but got `{}`", value))?;
+    let lat: f64 = lat
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid latitude: {}", e))?;
+    let lon: f64 = lon
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid longitude: {}", e))?;
 
-    // Configure TFHE for homomorphic integer encryption
-    let config = ConfigBuilder::default().build();
+    Point::from_crs(lat, lon, Crs::Wgs84).map_err(|e| e.to_string())
+}
+
+/// Formats a point as e.g. `Latitude 47.5596° N, Longitude 7.5886° E`.
+fn describe(point: &Point) -> String {
+    let ns = if point.lat < 0.0 { 'S' } else { 'N' };
+    let ew = if point.lon < 0.0 { 'W' } else { 'E' };
+    format!(
+        "Latitude {}° {}, Longitude {}° {}",
+        point.lat.abs(),
+        ns,
+        point.lon.abs(),
+        ew
+    )
+}
 
-    // Generate client and server keys
-    let (client_key, server_keys) = generate_keys(config);
+/// Encodes `points` with `mode`, centring `Planar` and `GroundMeters` on their mean.
+fn distance_mode(mode: Mode, points: &[Point]) -> DistanceMode {
+    let mean = |coordinate: fn(&Point) -> f64| {
+        points.iter().map(coordinate).sum::<f64>() / points.len() as f64
+    };
+
+    match mode {
+        Mode::Degrees => DistanceMode::Degrees,
+        Mode::Planar => DistanceMode::Planar {
+            reference_lat: mean(|point| point.lat),
+        },
+        Mode::GroundMeters => DistanceMode::GroundMeters {
+            central_lon: mean(|point| point.lon),
+        },
+    }
+}
+
+/// Fails if any of `points` is too far from `reference` for `squared_distance` to be exact.
+///
+/// Beyond that range the encrypted distance wraps and the answer would silently be wrong.
+fn check_range(
+    points: &[Point],
+    reference: &Point,
+    mode: DistanceMode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match points
+        .iter()
+        .find(|point| !squared_distance_fits(point, reference, mode))
+    {
+        None => Ok(()),
+        Some(point) => Err(format!(
+            "{} is too far from {} for an exact encrypted distance; \
+             keep points within about 6.5° of each other",
+            describe(point),
+            describe(reference)
+        )
+        .into()),
+    }
+}
+
+/// Loads or generates the client key, then loads or derives the server key and installs it
+/// for this thread.
+///
+/// With `--keys FILE` the client key is kept in `FILE` and the compressed server key next to
+/// it in `FILE.server`, so later runs skip server key generation, the slow part of start-up.
+fn setup_keys(options: &Options) -> Result<ClientKey, Box<dyn std::error::Error>> {
+    let reuse = options.keys.as_deref().is_some_and(Path::exists);
+    let client_key = match &options.keys {
+        Some(path) if reuse => {
+            if !options.json {
+                println!("Reading client key from {}...", path.display());
+            }
+            read_frame(BufReader::new(File::open(path)?))?
+        }
+        path => {
+            if !options.json {
+                println!("Generating keys...");
+            }
+
+            // Configure TFHE for homomorphic integer encryption
+            let config = ConfigBuilder::default().build();
+            let client_key = ClientKey::generate(config);
+            if let Some(path) = path {
+                write_frame(BufWriter::new(File::create(path)?), &client_key)?;
+            }
+            client_key
+        }
+    };
+
+    // A freshly generated client key never matches a server key left from an earlier one
+    let server_key = match options.keys.as_deref().map(server_key_path) {
+        Some(path) if reuse && path.exists() => {
+            if !options.json {
+                println!("Reading server key from {}...", path.display());
+            }
+            let compressed: CompressedServerKey = read_frame(BufReader::new(File::open(path)?))?;
+            compressed.decompress()
+        }
+        path => {
+            let compressed = CompressedServerKey::new(&client_key);
+            if let Some(path) = path {
+                write_frame(BufWriter::new(File::create(path)?), &compressed)?;
+            }
+            compressed.decompress()
+        }
+    };
 
     // Set server key for performing operations on encrypted data
-    set_server_key(server_keys);
-
-    // Example GPS coordinates for points X, Y, and Z (scaled by 10,000)
-    // Basel, Switzerland (Point X)
-    let x_lat = (47.5596 * 10_000.0) as u32; // Latitude of point X (47.5596° N)
-    let x_lon = (7.5886 * 10_000.0) as u32;  // Longitude of point X (7.5886° E)
-    println!("Point X (Basel): Latitude 47.5596° N, Longitude 7.5886° E");
-
-    // Lugano, Switzerland (Point Y)
-    let y_lat = (46.0037 * 10_000.0) as u32; // Latitude of point Y (46.0037° N)
-    let y_lon = (8.9511 * 10_000.0) as u32;  // Longitude of point Y (8.9511° E)
-    println!("Point Y (Lugano): Latitude 46.0037° N, Longitude 8.9511° E");
-
-    // Zurich, Switzerland (Point Z)
-    let z_lat = (47.3769 * 10_000.0) as u32; // Latitude of point Z (47.3769° N)
-    let z_lon = (8.5417 * 10_000.0) as u32;  // Longitude of point Z (8.5417° E)
-    println!("Point Z (Zurich): Latitude 47.3769° N, Longitude 8.5417° E");
-
-    // Encrypt the coordinates using the client key
-    let encrypted_x_lat = FheUint32::try_encrypt(x_lat, &client_key)?;
-    let encrypted_x_lon = FheUint32::try_encrypt(x_lon, &client_key)?;
-    let encrypted_y_lat = FheUint32::try_encrypt(y_lat, &client_key)?;
-    let encrypted_y_lon = FheUint32::try_encrypt(y_lon, &client_key)?;
-    let encrypted_z_lat = FheUint32::try_encrypt(z_lat, &client_key)?;
-    let encrypted_z_lon = FheUint32::try_encrypt(z_lon, &client_key)?;
-
-    println!("Everything is encrypted. Let's start the computation...");
+    set_server_key(server_key);
 
-    // Start timing the main computation
-    let start_time = Instant::now();
+    Ok(client_key)
+}
+
+/// Where the server key derived from the client key in `client_key_path` is kept.
+fn server_key_path(client_key_path: &Path) -> PathBuf {
+    let mut path = client_key_path.as_os_str().to_owned();
+    path.push(".server");
+    PathBuf::from(path)
+}
+
+fn print_duration(duration: Duration) {
+    // Print the computation duration (excluding key generation)
+    println!(
+        "Computation time (excluding key generation): {:?}",
+        duration
+    );
+}
+
+fn compare(
+    points: Option<Vec<Point>>,
+    divergence_log: Option<PathBuf>,
+    options: &Options,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let json = options.json;
+    // Without explicit points, compare Basel (X) and Lugano (Y) against Zurich (Z)
+    let (points, names) = match points {
+        Some(points) => (points, [None; 3]),
+        None => {
+            let demo = default_demo_points();
+            (
+                demo.iter().map(|(_, point)| *point).collect(),
+                demo.map(|(name, _)| Some(name)),
+            )
+        }
+    };
+    let labels: Vec<String> = ["X", "Y", "Z"]
+        .iter()
+        .zip(names)
+        .map(|(label, name)| match name {
+            Some(name) => format!("Point {} ({})", label, name),
+            None => format!("Point {}", label),
+        })
+        .collect();
+
+    let mode = distance_mode(options.mode.unwrap_or(Mode::Degrees), &points);
+    check_range(&points[..2], &points[2], mode)?;
+
+    if !json {
+        println!("Starting... Determining which point is closer to point Z...");
+    }
 
-    // Compute squared Euclidean distance from X to Z: (x_lat - z_lat)^2 + (x_lon - z_lon)^2
-    let dx_z = &encrypted_x_lat - &encrypted_z_lat;
-    let dy_z = &encrypted_x_lon - &encrypted_z_lon;
-    let dx_z2 = &dx_z * &dx_z;
-    let dy_z2 = &dy_z * &dy_z;
-    let distance_xz = &dx_z2 + &dy_z2;
+    let client_key = setup_keys(options)?;
 
-    // Compute squared Euclidean distance from Y to Z: (y_lat - z_lat)^2 + (y_lon - z_lon)^2
-    let dy_z_y = &encrypted_y_lat - &encrypted_z_lat;
-    let dx_z_y = &encrypted_y_lon - &encrypted_z_lon;
-    let dx_z_y2 = &dy_z_y * &dy_z_y;
-    let dy_z_y2 = &dx_z_y * &dx_z_y;
-    let distance_yz = &dx_z_y2 + &dy_z_y2;
+    if !json {
+        for (label, point) in labels.iter().zip(&points) {
+            println!("{}: {}", label, describe(point));
+        }
+    }
+
+    // Scale and encrypt the coordinates using the client key
+    let encrypted_x = ClientData::encrypt_with_mode(&points[0], mode, &client_key)?;
+    let encrypted_y = ClientData::encrypt_with_mode(&points[1], mode, &client_key)?;
+    let encrypted_z = ClientData::encrypt_with_mode(&points[2], mode, &client_key)?;
+
+    if !json {
+        println!("Everything is encrypted. Let's start the computation...");
+    }
+
+    // Start timing the main computation
+    let start_time = Instant::now();
 
-    // Compare distances homomorphically to determine which is smaller
-    let closer_x = &distance_xz.lt(&distance_yz); // true if X is closer, false if Y is closer
+    // Compare squared distances from X to Z and from Y to Z homomorphically
+    let closer_x = compare_distances(&encrypted_x, &encrypted_y, &encrypted_z); // true if X is closer, false if Y is closer
 
     // Stop timing the computation
     let duration = start_time.elapsed();
@@ -66,14 +307,117 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Decrypt results to determine the closer point
     let is_x_closer: bool = closer_x.decrypt(&client_key);
 
-    if is_x_closer {
-        println!("Point X (Basel) is closer to point Z (Zurich).");
-    } else {
-        println!("Point Y (Lugano) is closer to point Z (Zurich).");
+    // Record the triple if the encrypted model disagrees with the great-circle baseline
+    if let Some(path) = divergence_log {
+        let diverged =
+            DivergenceLog::new(path).check(&points[0], &points[1], &points[2], is_x_closer)?;
+        if diverged && !json {
+            println!("Result disagrees with the plaintext great-circle baseline; logged.");
+        }
     }
 
-    // Print the computation duration (excluding key generation)
-    println!("Computation time (excluding key generation): {:?}", duration);
+    if json {
+        let result = json!({
+            "closer": if is_x_closer { "X" } else { "Y" },
+            "computation_ms": duration.as_secs_f64() * 1000.0,
+        });
+        println!("{}", result);
+        return Ok(());
+    }
+
+    let closer = if is_x_closer { &labels[0] } else { &labels[1] };
+    println!(
+        "{} is closer to point {}.",
+        closer,
+        labels[2].trim_start_matches("Point ")
+    );
+    print_duration(duration);
+
+    Ok(())
+}
+
+fn nearest(
+    reference: &Point,
+    candidates: &[Point],
+    options: &Options,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let json = options.json;
+    let all_points: Vec<Point> = candidates.iter().chain([reference]).copied().collect();
+    let mode = distance_mode(options.mode.unwrap_or(Mode::Degrees), &all_points);
+    check_range(candidates, reference, mode)?;
+
+    let client_key = setup_keys(options)?;
+
+    // Scale and encrypt the reference and every candidate using the client key
+    let encrypted_reference = ClientData::encrypt_with_mode(reference, mode, &client_key)?;
+    let encrypted_candidates = ClientData::encrypt_batch(candidates, mode, &client_key)?;
+
+    if !json {
+        println!("Everything is encrypted. Let's start the computation...");
+    }
+
+    let start_time = Instant::now();
+    let encrypted_index = find_nearest(&encrypted_candidates, &encrypted_reference);
+    let duration = start_time.elapsed();
+
+    let index: u32 = encrypted_index.decrypt(&client_key);
+
+    if json {
+        let result = json!({
+            "nearest_index": index,
+            "computation_ms": duration.as_secs_f64() * 1000.0,
+        });
+        println!("{}", result);
+        return Ok(());
+    }
+
+    println!(
+        "Candidate {} ({}) is closest to the reference point.",
+        index,
+        describe(&candidates[index as usize])
+    );
+    print_duration(duration);
+
+    Ok(())
+}
+
+fn distance(a: &Point, b: &Point, options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let json = options.json;
+    // By default, scale longitudes by the cosine of the mean latitude so both axes measure
+    // ground distance
+    let mode = distance_mode(options.mode.unwrap_or(Mode::Planar), &[*a, *b]);
+    check_range(&[*a], b, mode)?;
+
+    let client_key = setup_keys(options)?;
+    let encrypted_a = ClientData::encrypt_with_mode(a, mode, &client_key)?;
+    let encrypted_b = ClientData::encrypt_with_mode(b, mode, &client_key)?;
+
+    if !json {
+        println!("Everything is encrypted. Let's start the computation...");
+    }
+
+    let start_time = Instant::now();
+    let distance = encrypted_distance(&encrypted_a, &encrypted_b);
+    let duration = start_time.elapsed();
+
+    let km = distance.decrypt_km(&client_key);
+    let degrees = km / KM_PER_DEGREE;
+
+    if json {
+        let result = json!({
+            "distance_deg": degrees,
+            "distance_km": km,
+            "computation_ms": duration.as_secs_f64() * 1000.0,
+        });
+        println!("{}", result);
+        return Ok(());
+    }
+
+    println!(
+        "Approximate distance: {:.1} km ({:.4}° of latitude).",
+        km, degrees
+    );
+    print_duration(duration);
 
     Ok(())
 }
@@ -0,0 +1,130 @@
+use crate::{Crs, GpsFheError, Point};
+
+/// Parses the position of an NMEA 0183 GGA or RMC sentence into a decimal-degree point.
+///
+/// Any talker id is accepted (`$GPGGA`, `$GNRMC`, ...). If the sentence carries a `*hh`
+/// checksum it must match. Sentences reporting no fix (GGA quality 0 or empty, RMC status
+/// `V`) are rejected rather than returning a stale or empty position.
+pub fn point_from_nmea(sentence: &str) -> Result<Point, GpsFheError> {
+    let invalid = |reason: &str| GpsFheError::InvalidNmea(reason.to_string());
+
+    let body = sentence
+        .trim()
+        .strip_prefix('$')
+        .ok_or_else(|| invalid("sentence does not start with `$`"))?;
+    let body = match body.split_once('*') {
+        Some((body, checksum)) => {
+            let expected = u8::from_str_radix(checksum, 16)
+                .map_err(|_| invalid("checksum is not two hex digits"))?;
+            if body.bytes().fold(0, |acc, byte| acc ^ byte) != expected {
+                return Err(invalid("checksum mismatch"));
+            }
+            body
+        }
+        None => body,
+    };
+
+    let fields: Vec<&str> = body.split(',').collect();
+    let kind = fields[0].get(2..).unwrap_or_default();
+    let (position, has_fix) = match kind {
+        "GGA" => (
+            2,
+            fields
+                .get(6)
+                .is_some_and(|quality| !quality.is_empty() && *quality != "0"),
+        ),
+        "RMC" => (3, fields.get(2) == Some(&"A")),
+        _ => return Err(invalid("only GGA and RMC sentences carry a position")),
+    };
+    if !has_fix {
+        return Err(invalid("receiver reports no fix"));
+    }
+
+    let field = |offset: usize| fields.get(position + offset).copied().unwrap_or_default();
+    let lat = nmea_degrees(field(0), field(1), 'N', 'S').ok_or_else(|| invalid("bad latitude"))?;
+    let lon = nmea_degrees(field(2), field(3), 'E', 'W').ok_or_else(|| invalid("bad longitude"))?;
+
+    Point::from_crs(lat, lon, Crs::Wgs84)
+}
+
+/// Converts a `(D)DDMM.MMMM` value and its hemisphere letter into signed decimal degrees.
+///
+/// Returns `None` for negative values and for minutes of 60 or more.
+fn nmea_degrees(value: &str, hemisphere: &str, positive: char, negative: char) -> Option<f64> {
+    let raw: f64 = value.parse().ok()?;
+    let degrees = (raw / 100.0).trunc();
+    let minutes = raw - degrees * 100.0;
+    if !(0.0..60.0).contains(&minutes) {
+        return None;
+    }
+    let magnitude = degrees + minutes / 60.0;
+
+    match hemisphere.chars().next()? {
+        c if c == positive => Some(magnitude),
+        c if c == negative => Some(-magnitude),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(point: Point, lat: f64, lon: f64) {
+        assert!((point.lat - lat).abs() < 1e-9, "latitude {}", point.lat);
+        assert!((point.lon - lon).abs() < 1e-9, "longitude {}", point.lon);
+    }
+
+    #[test]
+    fn parses_gga() {
+        let point =
+            point_from_nmea("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47")
+                .unwrap();
+        assert_near(point, 48.0 + 7.038 / 60.0, 11.0 + 31.0 / 60.0);
+    }
+
+    #[test]
+    fn parses_gga_in_the_southern_hemisphere() {
+        let point =
+            point_from_nmea("$GPGGA,123519,3351.600,S,15112.600,E,1,08,0.9,58.0,M,22.0,M,,*6C")
+                .unwrap();
+        assert_near(point, -33.86, 151.21);
+    }
+
+    #[test]
+    fn parses_rmc_in_the_western_hemisphere() {
+        let point =
+            point_from_nmea("$GNRMC,123519,A,4042.768,N,07400.360,W,022.4,084.4,230394,003.1,W*69")
+                .unwrap();
+        assert_near(point, 40.7128, -74.006);
+    }
+
+    #[test]
+    fn parses_rmc_south_and_west() {
+        let point =
+            point_from_nmea("$GPRMC,081836,A,2254.400,S,04310.800,W,000.0,360.0,130998,011.3,E*7A")
+                .unwrap();
+        assert_near(point, -(22.0 + 54.4 / 60.0), -43.18);
+    }
+
+    #[test]
+    fn rejects_gga_without_quality() {
+        let result =
+            point_from_nmea("$GPGGA,123519,4807.038,N,01131.000,E,,08,0.9,545.4,M,46.9,M,,*76");
+        assert!(matches!(result, Err(GpsFheError::InvalidNmea(_))));
+    }
+
+    #[test]
+    fn rejects_minutes_of_sixty_or_more() {
+        let result =
+            point_from_nmea("$GPGGA,123519,4899.000,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*4B");
+        assert!(matches!(result, Err(GpsFheError::InvalidNmea(_))));
+    }
+
+    #[test]
+    fn rejects_bad_checksum() {
+        let result =
+            point_from_nmea("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*48");
+        assert!(matches!(result, Err(GpsFheError::InvalidNmea(_))));
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use tfhe::prelude::*;
+use tfhe::{ClientKey, FheBool};
+
+use crate::GpsFheError;
+
+/// Number of ciphertexts decrypted per chunk unless configured otherwise.
+const DEFAULT_CHUNK_SIZE: usize = 64;
+
+/// Writes encrypted boolean results in the batched format read by `ResultStream`: a
+/// little-endian `u64` count followed by each result serialized with bincode.
+pub fn write_results<W: Write>(mut writer: W, results: &[FheBool]) -> Result<(), GpsFheError> {
+    writer
+        .write_all(&(results.len() as u64).to_le_bytes())
+        .map_err(|e| GpsFheError::Io(e.to_string()))?;
+    for result in results {
+        bincode::serialize_into(&mut writer, result).map_err(|e| match *e {
+            bincode::ErrorKind::Io(io) => GpsFheError::Io(io.to_string()),
+            other => GpsFheError::Serialization(other.to_string()),
+        })?;
+    }
+    Ok(())
+}
+
+/// Incrementally reads, decrypts and yields batched results as `(index, value)` pairs.
+///
+/// Ciphertexts are read and decrypted `chunk_size` at a time and dropped as soon as they are
+/// decrypted, so at most one chunk of ciphertexts is alive at once. With the `parallel`
+/// feature each chunk is decrypted on the rayon thread pool. If the reader ends before the
+/// declared number of results, the stream yields the values it did receive, then a
+/// `GpsFheError::TruncatedResults` item, and stops.
+pub struct ResultStream<'a, R> {
+    reader: R,
+    client_key: &'a ClientKey,
+    chunk_size: usize,
+    total: u64,
+    read: u64,
+    decrypted: VecDeque<(usize, bool)>,
+    pending_error: Option<GpsFheError>,
+    finished: bool,
+}
+
+impl<'a, R: Read> ResultStream<'a, R> {
+    /// Reads the result count from `reader` and prepares to stream the results.
+    pub fn from_reader(mut reader: R, client_key: &'a ClientKey) -> Result<Self, GpsFheError> {
+        let mut header = [0u8; 8];
+        reader
+            .read_exact(&mut header)
+            .map_err(|e| GpsFheError::MalformedResults(format!("missing result count: {}", e)))?;
+
+        Ok(ResultStream {
+            reader,
+            client_key,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            total: u64::from_le_bytes(header),
+            read: 0,
+            decrypted: VecDeque::new(),
+            pending_error: None,
+            finished: false,
+        })
+    }
+
+    /// Sets how many ciphertexts are read and decrypted at a time.
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be non-zero");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Number of results the stream declared in its header.
+    pub fn len(&self) -> u64 {
+        self.total
+    }
+
+    /// Whether the stream declared no results at all.
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// Reads and decrypts the next chunk, remembering any read error for after its values.
+    fn fill(&mut self) {
+        let first_index = self.read as usize;
+        let mut chunk = Vec::with_capacity(self.chunk_size);
+        while chunk.len() < self.chunk_size && self.read < self.total {
+            match bincode::deserialize_from::<_, FheBool>(&mut self.reader) {
+                Ok(result) => {
+                    chunk.push(result);
+                    self.read += 1;
+                }
+                Err(e) => {
+                    self.pending_error = Some(match *e {
+                        bincode::ErrorKind::Io(ref io) if io.kind() == ErrorKind::UnexpectedEof => {
+                            GpsFheError::TruncatedResults {
+                                expected: self.total,
+                                received: self.read,
+                            }
+                        }
+                        _ => GpsFheError::MalformedResults(e.to_string()),
+                    });
+                    break;
+                }
+            }
+        }
+
+        #[cfg(feature = "parallel")]
+        let results = chunk.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let results = chunk.iter();
+
+        let values: Vec<bool> = results
+            .map(|result| result.decrypt(self.client_key))
+            .collect();
+        self.decrypted.extend((first_index..).zip(values));
+    }
+}
+
+impl<R: Read> Iterator for ResultStream<'_, R> {
+    type Item = Result<(usize, bool), GpsFheError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.decrypted.pop_front() {
+                return Some(Ok(item));
+            }
+            if let Some(error) = self.pending_error.take() {
+                self.finished = true;
+                return Some(Err(error));
+            }
+            if self.finished || self.read == self.total {
+                return None;
+            }
+            self.fill();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::io;
+    use std::rc::Rc;
+
+    use crate::tests::keys;
+
+    /// A writer whose every write fails, like a closed socket.
+    struct BrokenPipe;
+
+    impl Write for BrokenPipe {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A reader that counts the bytes pulled through it.
+    struct CountingReader<'a> {
+        inner: &'a [u8],
+        read: Rc<Cell<usize>>,
+    }
+
+    impl Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.read.set(self.read.get() + n);
+            Ok(n)
+        }
+    }
+
+    /// Encrypts a known pattern of `len` booleans and writes it in the batched format.
+    fn fixture(client_key: &ClientKey, len: usize) -> (Vec<FheBool>, Vec<u8>) {
+        let results: Vec<FheBool> = (0..len)
+            .map(|i| FheBool::encrypt(i % 3 == 0, client_key))
+            .collect();
+        let mut bytes = Vec::new();
+        write_results(&mut bytes, &results).unwrap();
+        (results, bytes)
+    }
+
+    #[test]
+    fn write_failures_are_io_errors() {
+        assert!(matches!(
+            write_results(BrokenPipe, &[]),
+            Err(GpsFheError::Io(_))
+        ));
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn stream_matches_bulk_decryption() {
+        let client_key = keys();
+        let (results, bytes) = fixture(&client_key, 500);
+        let bulk: Vec<(usize, bool)> = results
+            .iter()
+            .map(|result| result.decrypt(&client_key))
+            .enumerate()
+            .collect();
+
+        let stream = ResultStream::from_reader(bytes.as_slice(), &client_key)
+            .unwrap()
+            .with_chunk_size(64);
+        assert_eq!(stream.len(), 500);
+        let streamed: Vec<(usize, bool)> = stream.map(Result::unwrap).collect();
+        assert_eq!(streamed, bulk);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn truncated_stream_yields_received_values_then_an_error() {
+        let client_key = keys();
+        let (results, bytes) = fixture(&client_key, 10);
+        // Cut the stream in the middle of the eighth ciphertext
+        let per_result = bincode::serialized_size(&results[0]).unwrap() as usize;
+        let truncated = &bytes[..8 + 7 * per_result + per_result / 2];
+
+        let items: Vec<_> = ResultStream::from_reader(truncated, &client_key)
+            .unwrap()
+            .with_chunk_size(4)
+            .collect();
+        assert_eq!(items.len(), 8);
+        for (i, item) in items[..7].iter().enumerate() {
+            assert_eq!(item, &Ok((i, i % 3 == 0)));
+        }
+        assert_eq!(
+            items[7],
+            Err(GpsFheError::TruncatedResults {
+                expected: 10,
+                received: 7
+            })
+        );
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn stream_reads_at_most_one_chunk_ahead() {
+        let client_key = keys();
+        let (results, bytes) = fixture(&client_key, 500);
+        let per_result = bincode::serialized_size(&results[0]).unwrap() as usize;
+        let read = Rc::new(Cell::new(0));
+        let reader = CountingReader {
+            inner: bytes.as_slice(),
+            read: Rc::clone(&read),
+        };
+
+        // Only the ciphertexts of the chunk holding the current result have been read, so at
+        // most 64 are alive however long the stream is
+        let mut stream = ResultStream::from_reader(reader, &client_key)
+            .unwrap()
+            .with_chunk_size(64);
+        for consumed in 1..=500usize {
+            stream.next().unwrap().unwrap();
+            let chunks = consumed.div_ceil(64);
+            assert!(read.get() <= 8 + chunks * 64 * per_result);
+        }
+        assert!(stream.next().is_none());
+    }
+}
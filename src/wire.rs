@@ -0,0 +1,274 @@
+use std::io::{Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tfhe::named::Named;
+use tfhe::safe_serialization::{safe_deserialize, safe_serialize};
+use tfhe::{CompressedServerKey, Unversionize};
+use tfhe_versionable::{Versionize, VersionsDispatch};
+
+use crate::{ClientData, GpsFheError};
+
+/// Largest frame `read_frame` accepts, in bytes. A compressed server key for the default
+/// parameters fits comfortably below it.
+pub const MAX_FRAME_LEN: u64 = 1 << 30;
+
+/// Largest encoding `ClientData::from_bytes` accepts, in bytes. Two `FheUint32` ciphertexts
+/// under the default parameters take about 525 KB.
+pub const MAX_CLIENT_DATA_LEN: u64 = 1 << 20;
+
+/// Version of the byte format written by `ClientData::to_bytes` and `write_frame`.
+///
+/// Every blob and frame starts with it as a little-endian `u16`. It is bumped whenever the
+/// encoding of a serialized type changes, so data from an older or newer build is rejected
+/// with `GpsFheError::UnsupportedVersion` instead of being mis-decoded.
+pub const WIRE_VERSION: u16 = 1;
+
+/// Everything a server needs to answer "is X closer to Z than Y": the evaluation key and the
+/// three encrypted points. The client key never leaves the client.
+#[derive(Serialize, Deserialize, Versionize)]
+#[versionize(QueryPackageVersions)]
+pub struct QueryPackage {
+    pub server_key: CompressedServerKey,
+    pub x: ClientData,
+    pub y: ClientData,
+    pub z: ClientData,
+}
+
+impl Named for QueryPackage {
+    const NAME: &'static str = "tfhe_gps_distance::QueryPackage";
+}
+
+impl Named for ClientData {
+    const NAME: &'static str = "tfhe_gps_distance::ClientData";
+}
+
+// tfhe's safe serialization only accepts versioned types; each has one version so far
+#[derive(VersionsDispatch)]
+pub enum QueryPackageVersions {
+    V0(QueryPackage),
+}
+
+#[derive(VersionsDispatch)]
+pub enum ClientDataVersions {
+    V0(ClientData),
+}
+
+impl ClientData {
+    /// Encodes the encrypted point, label included, as `WIRE_VERSION` followed by tfhe's safe
+    /// serialization, for storage or transport.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, GpsFheError> {
+        let mut bytes = WIRE_VERSION.to_le_bytes().to_vec();
+        safe_serialize(self, &mut bytes, MAX_CLIENT_DATA_LEN)
+            .map_err(|e| GpsFheError::Serialization(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Decodes a point written by `to_bytes`, reading at most `MAX_CLIENT_DATA_LEN` bytes. The
+    /// server key must match the client key that encrypted it for any computation on the
+    /// result to be meaningful.
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, GpsFheError> {
+        check_version(&mut bytes)?;
+        safe_deserialize(bytes, MAX_CLIENT_DATA_LEN).map_err(GpsFheError::Serialization)
+    }
+}
+
+/// Reads the leading version and fails unless it is `WIRE_VERSION`.
+fn check_version<R: Read>(mut reader: R) -> Result<(), GpsFheError> {
+    let mut version = [0u8; 2];
+    reader
+        .read_exact(&mut version)
+        .map_err(|e| GpsFheError::Io(e.to_string()))?;
+    match u16::from_le_bytes(version) {
+        WIRE_VERSION => Ok(()),
+        found => Err(GpsFheError::UnsupportedVersion {
+            found,
+            supported: WIRE_VERSION,
+        }),
+    }
+}
+
+/// Writes `value` as one frame: `WIRE_VERSION` and a `u64` length, both little-endian,
+/// followed by tfhe's safe serialization of the value.
+pub fn write_frame<W, T>(mut writer: W, value: &T) -> Result<(), GpsFheError>
+where
+    W: Write,
+    T: Serialize + Versionize + Named,
+{
+    let mut bytes = Vec::new();
+    safe_serialize(value, &mut bytes, MAX_FRAME_LEN)
+        .map_err(|e| GpsFheError::Serialization(e.to_string()))?;
+    writer
+        .write_all(&WIRE_VERSION.to_le_bytes())
+        .and_then(|_| writer.write_all(&(bytes.len() as u64).to_le_bytes()))
+        .and_then(|_| writer.write_all(&bytes))
+        .and_then(|_| writer.flush())
+        .map_err(|e| GpsFheError::Io(e.to_string()))
+}
+
+/// Reads one frame written by `write_frame`, rejecting other versions and declared lengths
+/// above `MAX_FRAME_LEN` before reading the payload.
+///
+/// The payload buffer only grows as bytes actually arrive, so a peer declaring a large frame
+/// and then sending nothing costs no memory, and the payload is decoded with tfhe's safe
+/// deserialization bounded by the declared length.
+pub fn read_frame<R, T>(mut reader: R) -> Result<T, GpsFheError>
+where
+    R: Read,
+    T: DeserializeOwned + Unversionize + Named,
+{
+    check_version(&mut reader)?;
+    let mut header = [0u8; 8];
+    reader
+        .read_exact(&mut header)
+        .map_err(|e| GpsFheError::Io(e.to_string()))?;
+    let len = u64::from_le_bytes(header);
+    if len > MAX_FRAME_LEN {
+        return Err(GpsFheError::Serialization(format!(
+            "frame of {} bytes exceeds the {} byte limit",
+            len, MAX_FRAME_LEN
+        )));
+    }
+
+    let mut bytes = Vec::new();
+    reader
+        .take(len)
+        .read_to_end(&mut bytes)
+        .map_err(|e| GpsFheError::Io(e.to_string()))?;
+    if (bytes.len() as u64) < len {
+        return Err(GpsFheError::Io(format!(
+            "frame ended after {} of {} bytes",
+            bytes.len(),
+            len
+        )));
+    }
+
+    safe_deserialize(bytes.as_slice(), len).map_err(GpsFheError::Serialization)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use tfhe::prelude::*;
+    use tfhe::{CompressedFheBool, CompressedFheUint32, FheBool, FheUint32};
+
+    use crate::tests::{encrypt, keys};
+    use crate::{compare_distances, default_demo_points};
+
+    #[test]
+    fn from_bytes_rejects_another_version() {
+        let mut bytes = (WIRE_VERSION + 1).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0; 16]);
+
+        assert!(matches!(
+            ClientData::from_bytes(&bytes),
+            Err(GpsFheError::UnsupportedVersion { found, supported })
+                if found == WIRE_VERSION + 1 && supported == WIRE_VERSION
+        ));
+    }
+
+    #[test]
+    fn read_frame_rejects_another_version() {
+        let mut frame = (WIRE_VERSION + 1).to_le_bytes().to_vec();
+        frame.extend_from_slice(&0u64.to_le_bytes());
+
+        assert!(matches!(
+            read_frame::<_, FheBool>(frame.as_slice()),
+            Err(GpsFheError::UnsupportedVersion { .. })
+        ));
+    }
+
+    fn frame_header(len: u64) -> Vec<u8> {
+        let mut header = WIRE_VERSION.to_le_bytes().to_vec();
+        header.extend_from_slice(&len.to_le_bytes());
+        header
+    }
+
+    #[test]
+    fn read_frame_rejects_oversized_length_before_reading() {
+        let header = frame_header(MAX_FRAME_LEN + 1);
+        assert!(matches!(
+            read_frame::<_, FheBool>(header.as_slice()),
+            Err(GpsFheError::Serialization(_))
+        ));
+    }
+
+    #[test]
+    fn read_frame_does_not_trust_declared_length() {
+        // A peer declaring the largest allowed frame and sending a few bytes must not cost a
+        // gigabyte; the read fails as soon as the data runs out
+        let mut frame = frame_header(MAX_FRAME_LEN);
+        frame.extend_from_slice(&[0; 8]);
+        assert!(matches!(
+            read_frame::<_, FheBool>(frame.as_slice()),
+            Err(GpsFheError::Io(reason)) if reason.contains("after 8 of")
+        ));
+    }
+
+    #[test]
+    fn read_frame_rejects_garbage_payload() {
+        let mut frame = frame_header(16);
+        frame.extend_from_slice(&[0xff; 16]);
+        assert!(matches!(
+            read_frame::<_, FheBool>(frame.as_slice()),
+            Err(GpsFheError::Serialization(_))
+        ));
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn frames_round_trip_over_loopback() {
+        let client_key = keys();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let sent = FheBool::encrypt(true, &client_key);
+        let writer =
+            thread::spawn(move || write_frame(TcpStream::connect(address).unwrap(), &sent));
+        let (stream, _) = listener.accept().unwrap();
+        let received: FheBool = read_frame(stream).unwrap();
+        writer.join().unwrap().unwrap();
+
+        let value: bool = received.decrypt(&client_key);
+        assert!(value);
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn client_data_round_trips_through_bytes() {
+        let client_key = keys();
+        let [x, y, z] = default_demo_points().map(|(name, point)| {
+            let bytes = encrypt(point.lat, point.lon, &client_key)
+                .with_name(name)
+                .to_bytes()
+                .unwrap();
+            ClientData::from_bytes(&bytes).unwrap()
+        });
+        assert_eq!(x.name.as_deref(), Some("Basel"));
+
+        let x_closer: bool = compare_distances(&x, &y, &z).decrypt(&client_key);
+        assert!(x_closer);
+    }
+
+    fn size<T: Serialize>(value: &T) -> u64 {
+        bincode::serialized_size(value).unwrap()
+    }
+
+    #[test]
+    #[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+    fn compressed_forms_are_smaller() {
+        let client_key = keys();
+        let lat = 1_375_596u32;
+        let compressed_u32 = size(&CompressedFheUint32::encrypt(lat, &client_key));
+
+        assert!(compressed_u32 < size(&FheUint32::encrypt(lat, &client_key)));
+        // There is no compressed `ClientData`; its two coordinates compress individually
+        assert!(2 * compressed_u32 < size(&encrypt(47.5596, 7.5886, &client_key)));
+        assert!(
+            size(&CompressedFheBool::encrypt(true, &client_key))
+                < size(&FheBool::encrypt(true, &client_key))
+        );
+    }
+}
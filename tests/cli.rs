@@ -0,0 +1,109 @@
+use std::fs;
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+const BASEL: &str = "47.5596,7.5886";
+const LUGANO: &str = "46.0037,8.9511";
+const ZURICH: &str = "47.3769,8.5417";
+const WINTERTHUR: &str = "47.4988,8.7237";
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_tfhe-gps-distance"))
+        .args(args)
+        .output()
+        .expect("binary runs")
+}
+
+/// Runs a subcommand with `--json`, checks it succeeded and parses its output.
+fn run_json(args: &[&str]) -> Value {
+    let output = run(&[&["--json"], args].concat());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_str(&stdout).unwrap_or_else(|e| panic!("{}: {}", e, stdout))
+}
+
+#[test]
+fn compare_rejects_points_too_far_apart() {
+    // New York and Paris against Zurich would wrap and report New York as closer
+    let output = run(&["compare", "40.7128,-74.006", "48.8566,2.3522", ZURICH]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("too far"), "{}", stderr);
+}
+
+#[test]
+fn distance_rejects_points_too_far_apart() {
+    // London to Tokyo would wrap and decrypt to a few hundred kilometers
+    let output = run(&["distance", "51.5074,-0.1278", "35.6762,139.6503"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("too far"), "{}", stderr);
+}
+
+#[test]
+#[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+fn compare_defaults_to_the_swiss_demo_points() {
+    let output = run(&["compare"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("Point X (Basel) is closer to point Z (Zurich)."),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+#[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+fn compare_reports_the_closer_point() {
+    let result = run_json(&["--mode", "planar", "compare", LUGANO, BASEL, ZURICH]);
+    assert_eq!(result["closer"], "Y");
+}
+
+#[test]
+#[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+fn nearest_and_distance_share_a_key_file() {
+    let keys =
+        std::env::temp_dir().join(format!("tfhe-gps-distance-cli-{}.key", std::process::id()));
+    let server_keys = keys.with_file_name(format!(
+        "{}.server",
+        keys.file_name().unwrap().to_str().unwrap()
+    ));
+    let keys_arg = keys.to_str().unwrap();
+    let _ = fs::remove_file(&keys);
+    let _ = fs::remove_file(&server_keys);
+
+    let result = run_json(&[
+        "--keys", keys_arg, "nearest", ZURICH, LUGANO, WINTERTHUR, BASEL,
+    ]);
+    assert_eq!(result["nearest_index"], 1);
+    let written = |path| fs::metadata(path).unwrap().modified().unwrap();
+    let (client_written, server_written) = (written(&keys), written(&server_keys));
+
+    // The second run reads both keys written by the first instead of generating them
+    let output = run(&["--keys", keys_arg, "distance", BASEL, ZURICH]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("Reading server key"), "{}", stdout);
+    assert!(!stdout.contains("Generating keys"), "{}", stdout);
+    assert_eq!(written(&keys), client_written);
+    assert_eq!(written(&server_keys), server_written);
+    let km: f64 = stdout
+        .split("Approximate distance: ")
+        .nth(1)
+        .and_then(|rest| rest.split(' ').next())
+        .and_then(|km| km.parse().ok())
+        .unwrap_or_else(|| panic!("no distance in {}", stdout));
+    assert!((74.0..=75.0).contains(&km), "decrypted {} km", km);
+
+    fs::remove_file(&keys).unwrap();
+    fs::remove_file(&server_keys).unwrap();
+}
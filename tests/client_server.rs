@@ -0,0 +1,44 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+/// Kills the server when the test ends, whether it passes or not.
+struct Server(std::process::Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[test]
+#[ignore = "runs real FHE; use `cargo test --release -- --ignored`"]
+fn client_gets_answer_from_server_over_loopback() {
+    let mut server = Server(
+        Command::new(env!("CARGO_BIN_EXE_server"))
+            .args(["--listen", "127.0.0.1:0"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("server starts"),
+    );
+
+    // The server prints the address it bound, including the port the OS picked. Keep its
+    // stdout open afterwards, or its later progress lines fail with a broken pipe
+    let mut server_stdout = BufReader::new(server.0.stdout.take().unwrap());
+    let mut banner = String::new();
+    server_stdout.read_line(&mut banner).unwrap();
+    let address = banner
+        .trim()
+        .strip_prefix("Listening on ")
+        .and_then(|rest| rest.strip_suffix("..."))
+        .unwrap_or_else(|| panic!("unexpected server banner: {}", banner));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_client"))
+        .args(["--server", address])
+        .output()
+        .expect("client runs");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "client failed: {}", stdout);
+    assert!(stdout.contains("Basel is closer to Zurich."), "{}", stdout);
+}